@@ -7,7 +7,7 @@ use osrm_binding::algorithm::Algorithm;
 use osrm_binding::osrm_engine::OsrmEngine;
 use osrm_binding::point::Point;
 use osrm_binding::route::RouteRequestBuilder;
-use osrm_binding::tables::TableRequest;
+use osrm_binding::tables::TableRequestBuilder;
 
 fn calculate_table_successfully(c: &mut Criterion) {
     dotenv().expect(".env file could not be read");
@@ -15,15 +15,16 @@ fn calculate_table_successfully(c: &mut Criterion) {
         .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
     let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
 
-    let request = TableRequest {
-        sources: vec![
-            Point { longitude: 2.3522, latitude: 48.8566 } // Paris
-        ],
-        destinations: vec![
-            Point { longitude: 5.3698, latitude: 43.2965 }, // Marseille
-            Point { longitude: 4.8357, latitude: 45.7640 }  // Lyon
-        ]
-    };
+    let request = TableRequestBuilder::default()
+        .sources(vec![
+            Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() } // Paris
+        ])
+        .destinations(vec![
+            Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+            Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }  // Lyon
+        ])
+        .build()
+        .expect("Failed to build TableRequest");
 
     c.bench_function("calculate_table_successfully", |b| {
         b.iter(|| {
@@ -39,7 +40,7 @@ fn calculate_route_successfully(c: &mut Criterion) {
     let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
 
     let request = RouteRequestBuilder::default()
-        .points(vec![Point { longitude: 2.3522, latitude: 48.8566 }, Point { longitude: 5.3698, latitude: 43.2965 }])
+        .points(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }])
         .build()
         .expect("Failed to build RouteRequest");
 
@@ -56,8 +57,8 @@ fn calculate_simple_route_successfully(c: &mut Criterion) {
         .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
     let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
 
-    let start = Point { longitude: 2.3522, latitude: 48.8566 };
-    let end = Point { longitude: 5.3698, latitude: 43.2965 };
+    let start = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+    let end = Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() };
 
     c.bench_function("calculate_simple_route_successfully", |b| {
         b.iter(|| {
@@ -78,12 +79,13 @@ fn calculate_table_10_successfully_mld(c: &mut Criterion) {
 
     c.bench_function("calculate_table_10_successfully_mld", |b| {
         b.iter(|| {
-            let request = TableRequest {
-                sources: vec![
-                    Point { longitude: base_lon, latitude: base_lat }
-                ],
-                destinations: (0..10).map( |_| Point { longitude: base_lon + rng.random_range(-0.1..0.1), latitude: base_lat + rng.random_range(-0.1..0.1) }).collect(),
-            };
+            let request = TableRequestBuilder::default()
+                .sources(vec![
+                    Point { longitude: base_lon, latitude: base_lat, ..Default::default() }
+                ])
+                .destinations((0..10).map( |_| Point { longitude: base_lon + rng.random_range(-0.1..0.1), latitude: base_lat + rng.random_range(-0.1..0.1), ..Default::default() }).collect())
+                .build()
+                .expect("Failed to build TableRequest");
 
             let _response = engine.table(request.clone()).expect("Table request failed");
         });
@@ -102,12 +104,13 @@ fn calculate_table_100_successfully_mld(c: &mut Criterion) {
 
     c.bench_function("calculate_table_100_successfully_mld", |b| {
         b.iter(|| {
-            let request = TableRequest {
-                sources: vec![
-                    Point { longitude: base_lon, latitude: base_lat }
-                ],
-                destinations: (0..100).map( |_| Point { longitude: base_lon + rng.random_range(-0.1..0.1), latitude: base_lat + rng.random_range(-0.1..0.1) }).collect(),
-            };
+            let request = TableRequestBuilder::default()
+                .sources(vec![
+                    Point { longitude: base_lon, latitude: base_lat, ..Default::default() }
+                ])
+                .destinations((0..100).map( |_| Point { longitude: base_lon + rng.random_range(-0.1..0.1), latitude: base_lat + rng.random_range(-0.1..0.1), ..Default::default() }).collect())
+                .build()
+                .expect("Failed to build TableRequest");
 
             let _response = engine.table(request.clone()).expect("Table request failed");
         });
@@ -131,10 +134,12 @@ fn calculate_multiple_routes_around_paris_10km_mld(c: &mut Criterion) {
             let start = Point {
                 latitude: base_lat + rng.random_range(-0.1..0.1),
                 longitude: base_lon + rng.random_range(-0.1..0.1),
+                ..Default::default()
             };
             let end = Point {
                 latitude: base_lat + rng.random_range(-0.1..0.1),
                 longitude: base_lon + rng.random_range(-0.1..0.1),
+                ..Default::default()
             };
 
             let _response = engine.simple_route(start, end)
@@ -161,10 +166,12 @@ fn calculate_multiple_routes_around_paris_100km_mld(c: &mut Criterion) {
             let start = Point {
                 latitude: base_lat + rng.random_range(-1..1) as f64,
                 longitude: base_lon + rng.random_range(-1..1) as f64,
+                ..Default::default()
             };
             let end = Point {
                 latitude: base_lat + rng.random_range(-1..1) as f64,
                 longitude: base_lon + rng.random_range(-1..1) as f64,
+                ..Default::default()
             };
 
             let _response = engine.simple_route(start, end)
@@ -191,10 +198,12 @@ fn calculate_multiple_routes_around_paris_10km_ch(c: &mut Criterion) {
             let start = Point {
                 latitude: base_lat + rng.random_range(-0.1..0.1),
                 longitude: base_lon + rng.random_range(-0.1..0.1),
+                ..Default::default()
             };
             let end = Point {
                 latitude: base_lat + rng.random_range(-0.1..0.1),
                 longitude: base_lon + rng.random_range(-0.1..0.1),
+                ..Default::default()
             };
 
             let _response = engine.simple_route(start, end)
@@ -221,10 +230,12 @@ fn calculate_multiple_routes_around_paris_100km_ch(c: &mut Criterion) {
             let start = Point {
                 latitude: base_lat + rng.random_range(-1..1) as f64,
                 longitude: base_lon + rng.random_range(-1..1) as f64,
+                ..Default::default()
             };
             let end = Point {
                 latitude: base_lat + rng.random_range(-1..1) as f64,
                 longitude: base_lon + rng.random_range(-1..1) as f64,
+                ..Default::default()
             };
 
             let _response = engine.simple_route(start, end)
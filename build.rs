@@ -1,30 +1,185 @@
-use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
+/// Release built when nothing selects a version.
+const DEFAULT_OSRM_VERSION: &str = "6.0.0";
+
+/// Where the OSRM headers and libraries come from, checked in this order.
+enum OsrmSource {
+    /// `OSRM_PREFIX`: an existing `make install` prefix.
+    Prefix(PathBuf),
+    /// `OSRM_SOURCE_DIR`: an unpacked osrm-backend checkout, built with cmake.
+    SourceDir(PathBuf),
+    /// `OSRM_SOURCE_TARBALL`: a local osrm-backend `.tar.gz`, unpacked and built with cmake.
+    Tarball(PathBuf),
+    /// `download` feature: fetch the release tarball from GitHub and build it with cmake.
+    Download,
+    /// Default: a system libosrm found through pkg-config.
+    System,
+}
+
+/// Include directories and library directory of a usable OSRM installation.
+struct OsrmInstall {
+    includes: Vec<PathBuf>,
+    lib_dir: Option<PathBuf>,
+    static_libs: bool,
+    /// Version reported by pkg-config or the installed `libosrm.pc`, if any.
+    version: Option<String>,
+}
 
 fn main() {
+    for var in ["OSRM_PREFIX", "OSRM_SOURCE_DIR", "OSRM_SOURCE_TARBALL", "OSRM_VERSION"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    println!("cargo:rerun-if-changed=src/wrapper.cpp");
+
+    println!("cargo:rustc-check-cfg=cfg(osrm_v5)");
+
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let requested_version = requested_version();
 
-    let osrm_url = "https://github.com/Project-OSRM/osrm-backend/archive/refs/tags/v6.0.0.tar.gz";
+    let install = match select_source() {
+        OsrmSource::Prefix(prefix) => {
+            eprintln!("Using prebuilt OSRM from {}", prefix.display());
+            OsrmInstall {
+                includes: vec![prefix.join("include"), prefix.join("include/osrm")],
+                lib_dir: Some(prefix.join("lib")),
+                static_libs: has_only_static_libs(&prefix.join("lib")),
+                version: installed_version(&prefix.join("lib")),
+            }
+        }
+        OsrmSource::SourceDir(path) => build_from_source(&path),
+        OsrmSource::Tarball(tarball) => {
+            eprintln!("Decompressing OSRM source from {}...", tarball.display());
+            let file = std::fs::File::open(&tarball).expect("Failed to open OSRM_SOURCE_TARBALL");
+            unpack(file, &out_dir);
+            build_from_source(&find_osrm_source(&out_dir))
+        }
+        OsrmSource::Download => {
+            download(requested_version.as_deref().unwrap_or(DEFAULT_OSRM_VERSION), &out_dir);
+            build_from_source(&find_osrm_source(&out_dir))
+        }
+        OsrmSource::System => probe_system(),
+    };
 
-    eprintln!("Downloading OSRM source from {}...", osrm_url);
+    if let (Some(requested), Some(installed)) = (&requested_version, &install.version)
+        && major_version(requested) != major_version(installed)
+    {
+        panic!(
+            "OSRM {} was requested through OSRM_VERSION or an `osrm-*` feature, but the OSRM \
+             installation found is version {}. Request a matching version or point the build \
+             to another installation.",
+            requested, installed
+        );
+    }
+    let version = install
+        .version
+        .clone()
+        .or(requested_version)
+        .unwrap_or_else(|| DEFAULT_OSRM_VERSION.to_owned());
+    let major = major_version(&version);
+    eprintln!("Building against OSRM {}", version);
+    if major == 5 {
+        println!("cargo:rustc-cfg=osrm_v5");
+    }
 
-    let mut response = reqwest::blocking::get(osrm_url).unwrap();
-    let mut buffer = Vec::new();
-    response.copy_to(&mut buffer).unwrap();
+    let mut wrapper = cc::Build::new();
+    wrapper
+        .cpp(true)
+        .file("src/wrapper.cpp")
+        .flag("-std=c++17")
+        .define("FMT_HEADER_ONLY", None)
+        .define("OSRM_BINDING_VERSION_MAJOR", major.to_string().as_str());
+    for include in &install.includes {
+        wrapper.include(include);
+    }
+    wrapper.compile("osrm_wrapper");
 
-    eprintln!("Decompressing OSRM source...");
-    let cursor = Cursor::new(buffer);
-    let tar_gz = flate2::read::GzDecoder::new(cursor);
-    let mut archive = tar::Archive::new(tar_gz);
-    archive.unpack(&out_dir).unwrap();
+    link(&install);
+}
+
+fn select_source() -> OsrmSource {
+    if let Some(prefix) = std::env::var_os("OSRM_PREFIX") {
+        return OsrmSource::Prefix(prefix.into());
+    }
+    if let Some(dir) = std::env::var_os("OSRM_SOURCE_DIR") {
+        return OsrmSource::SourceDir(dir.into());
+    }
+    if let Some(tarball) = std::env::var_os("OSRM_SOURCE_TARBALL") {
+        return OsrmSource::Tarball(tarball.into());
+    }
+    if std::env::var_os("CARGO_FEATURE_DOWNLOAD").is_some() {
+        return OsrmSource::Download;
+    }
+    OsrmSource::System
+}
+
+/// Version asked for through `OSRM_VERSION` or one of the `osrm-*` features.
+fn requested_version() -> Option<String> {
+    let features: Vec<&str> = [("CARGO_FEATURE_OSRM_5_27", "5.27.1"), ("CARGO_FEATURE_OSRM_6_0", "6.0.0")]
+        .into_iter()
+        .filter(|(feature, _)| std::env::var_os(feature).is_some())
+        .map(|(_, version)| version)
+        .collect();
+    if features.len() > 1 {
+        panic!("The `osrm-5-27` and `osrm-6-0` features are mutually exclusive");
+    }
+
+    std::env::var("OSRM_VERSION")
+        .ok()
+        .map(|version| version.trim_start_matches('v').to_owned())
+        .or(features.first().map(|version| version.to_string()))
+}
+
+fn major_version(version: &str) -> u32 {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(5), Some(minor)) if minor >= 27 => 5,
+        (Some(6), Some(_)) => 6,
+        _ => panic!("Unsupported OSRM version {}, expected 5.27 or 6.x", version),
+    }
+}
+
+/// Whether an installation ships `libosrm.a` without a shared library, which the linker would prefer.
+fn has_only_static_libs(lib_dir: &Path) -> bool {
+    lib_dir.join("libosrm.a").exists()
+        && !["libosrm.so", "libosrm.dylib"].iter().any(|shared| lib_dir.join(shared).exists())
+}
+
+/// Reads the version of an installation from the `libosrm.pc` file installed next to its libraries.
+fn installed_version(lib_dir: &Path) -> Option<String> {
+    let pc = std::fs::read_to_string(lib_dir.join("pkgconfig/libosrm.pc")).ok()?;
+    pc.lines()
+        .find_map(|line| line.strip_prefix("Version:"))
+        .map(|version| version.trim().trim_start_matches('v').to_owned())
+}
+
+fn probe_system() -> OsrmInstall {
+    // Link flags are emitted by `link`, pkg-config only locates the installation.
+    let library = pkg_config::Config::new()
+        .cargo_metadata(false)
+        .probe("libosrm")
+        .unwrap_or_else(|e| {
+            panic!(
+                "libosrm was not found through pkg-config ({}). Install OSRM, or set OSRM_PREFIX, \
+                 OSRM_SOURCE_DIR or OSRM_SOURCE_TARBALL, or enable the `download` feature.",
+                e
+            )
+        });
+
+    OsrmInstall {
+        includes: library.include_paths,
+        lib_dir: library.link_paths.into_iter().next(),
+        static_libs: false,
+        version: Some(library.version.trim().trim_start_matches('v').to_owned()),
+    }
+}
 
-    let osrm_source_path = find_osrm_source(&out_dir);
+fn build_from_source(osrm_source_path: &Path) -> OsrmInstall {
     eprintln!("OSRM source path: {}", osrm_source_path.display());
 
     let cxx_flags = "-Wno-array-bounds -Wno-uninitialized -Wno-stringop-overflow -std=c++17 -Wno-error";
 
-    let dst = cmake::Config::new(&osrm_source_path)
+    let dst = cmake::Config::new(osrm_source_path)
         .env("CXXFLAGS", cxx_flags)
         .define("CMAKE_CXX_STANDARD", "17")
         .define("CMAKE_CXX_STANDARD_REQUIRED", "ON")
@@ -33,28 +188,68 @@ fn main() {
         .define("ENABLE_LTO", "Off")
         .build();
 
-    cc::Build::new()
-        .cpp(true)
-        .file("src/wrapper.cpp")
-        .flag("-std=c++17")
-        .include(dst.join("include"))
-        .include(osrm_source_path.join("include"))
-        .include(osrm_source_path.join("third_party/fmt/include"))
-        .define("FMT_HEADER_ONLY", None)
-        .compile("osrm_wrapper");
+    OsrmInstall {
+        includes: vec![
+            dst.join("include"),
+            osrm_source_path.join("include"),
+            osrm_source_path.join("third_party/fmt/include"),
+            osrm_source_path.join("third_party/flatbuffers/include"),
+        ],
+        lib_dir: Some(dst.join("lib")),
+        static_libs: true,
+        version: installed_version(&dst.join("lib")),
+    }
+}
+
+fn osrm_url(version: &str) -> String {
+    format!("https://github.com/Project-OSRM/osrm-backend/archive/refs/tags/v{}.tar.gz", version)
+}
 
-    let lib_path = dst.join("lib");
-    println!("cargo:rustc-link-search=native={}", lib_path.display());
+#[cfg(feature = "download")]
+fn download(version: &str, out_dir: &Path) {
+    let url = osrm_url(version);
+    eprintln!("Downloading OSRM source from {}...", url);
+
+    let mut response = reqwest::blocking::get(&url).unwrap();
+    let mut buffer = Vec::new();
+    response.copy_to(&mut buffer).unwrap();
+
+    eprintln!("Decompressing OSRM source...");
+    unpack(std::io::Cursor::new(buffer), out_dir);
+}
+
+#[cfg(not(feature = "download"))]
+fn download(version: &str, _out_dir: &Path) {
+    panic!("Downloading {} requires the `download` feature", osrm_url(version));
+}
+
+fn unpack(reader: impl std::io::Read, out_dir: &Path) {
+    let tar_gz = flate2::read::GzDecoder::new(reader);
+    let mut archive = tar::Archive::new(tar_gz);
+    archive.unpack(out_dir).unwrap();
+}
+
+fn link(install: &OsrmInstall) {
+    if let Some(lib_dir) = &install.lib_dir {
+        println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    }
 
     println!("cargo:rustc-link-lib=static=osrm_wrapper");
-    println!("cargo:rustc-link-lib=static=osrm");
-    println!("cargo:rustc-link-lib=static=osrm_store");
-    println!("cargo:rustc-link-lib=static=osrm_extract");
-    println!("cargo:rustc-link-lib=static=osrm_partition");
-    println!("cargo:rustc-link-lib=static=osrm_update");
-    println!("cargo:rustc-link-lib=static=osrm_guidance");
-    println!("cargo:rustc-link-lib=static=osrm_customize");
-    println!("cargo:rustc-link-lib=static=osrm_contract");
+
+    // Source builds always produce static archives, prefixes may hold either.
+    let kind = if install.static_libs { "static" } else { "dylib" };
+    for lib in [
+        "osrm",
+        "osrm_store",
+        "osrm_extract",
+        "osrm_partition",
+        "osrm_update",
+        "osrm_guidance",
+        "osrm_customize",
+        "osrm_contract",
+    ] {
+        println!("cargo:rustc-link-lib={}={}", kind, lib);
+    }
 
     println!("cargo:rustc-link-lib=dylib=boost_thread");
     println!("cargo:rustc-link-lib=dylib=boost_filesystem");
@@ -66,7 +261,6 @@ fn main() {
     println!("cargo:rustc-link-lib=dylib=z");
     println!("cargo:rustc-link-lib=dylib=bz2");
     println!("cargo:rustc-link-lib=dylib=expat");
-
 }
 
 fn find_osrm_source(path: &Path) -> PathBuf {
@@ -78,4 +272,4 @@ fn find_osrm_source(path: &Path) -> PathBuf {
         }
     }
     panic!("Could not find OSRM source directory");
-}
\ No newline at end of file
+}
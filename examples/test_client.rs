@@ -2,7 +2,7 @@ use std::time::Instant;
 use osrm_binding::algorithm::Algorithm;
 use osrm_binding::osrm_engine::OsrmEngine;
 use osrm_binding::point::Point;
-use osrm_binding::tables::TableRequest;
+use osrm_binding::tables::TableRequestBuilder;
 
 fn main() {
     dotenvy::dotenv().expect(".env file could not be read");
@@ -12,15 +12,16 @@ fn main() {
 
     let start = Instant::now();  // Capture start time
     (0..100).for_each(|_| {
-        let request = TableRequest {
-            sources: vec![
-                Point { longitude: 2.3522, latitude: 48.8566 } // Paris
-            ],
-            destinations: vec![
-                Point { longitude: 5.3698, latitude: 43.2965 }, // Marseille
-                Point { longitude: 4.8357, latitude: 45.7640 }  // Lyon
-            ]
-        };
+        let request = TableRequestBuilder::default()
+            .sources(vec![
+                Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() } // Paris
+            ])
+            .destinations(vec![
+                Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+                Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }  // Lyon
+            ])
+            .build()
+            .expect("Failed to build TableRequest");
         let _ = engine.table(request).expect("Table request failed");
     });
     let duration = start.elapsed();  // Calculate the elapsed time
@@ -28,7 +29,7 @@ fn main() {
 
     let start = Instant::now();  // Capture start time
     (0..100).for_each(|_| {
-        let _ = engine.simple_route(Point { longitude: 2.3522, latitude: 48.8566 }, Point {  longitude: 5.3698, latitude: 43.2965 }).expect("route request failed");
+        let _ = engine.simple_route(Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }).expect("route request failed");
     });
     let duration = start.elapsed();  // Calculate the elapsed time
     println!("Time taken for 100 simple route: {:?}", duration);
@@ -0,0 +1,253 @@
+//! Async front-end of `OsrmEngine` for async services. Requests run on a dedicated pool of
+//! threads so that the blocking OSRM calls never stall the async runtime.
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use derive_builder::Builder;
+use tokio::sync::{oneshot, Semaphore};
+use crate::errors::OsrmError;
+use crate::nearest::{NearestRequest, NearestResponse};
+use crate::osrm_engine::OsrmEngine;
+use crate::route::{RouteRequest, RouteResponse};
+use crate::tables::{TableRequest, TableResponse};
+use crate::trip::{TripRequest, TripResponse};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Builder, Clone)]
+pub struct PoolConfig {
+    /// Requests computed in parallel, one per available core by default.
+    #[builder(default = "std::thread::available_parallelism().map_or(1, |threads| threads.get())")]
+    pub threads: usize,
+    /// Requests waiting for a thread. Once full, new requests wait before being queued.
+    #[builder(default = "64")]
+    pub queue_capacity: usize,
+}
+
+/// Runs the requests of an `OsrmEngine` on its own bounded thread pool.
+///
+/// Dropping a returned future before its request started removes it from the queue, a request
+/// already running completes and its result is discarded. Dropping the last clone does not block:
+/// the queued requests complete in the background, then the workers exit and release the engine.
+#[derive(Clone)]
+pub struct AsyncOsrmEngine {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    engine: Arc<OsrmEngine>,
+    /// Queued and running requests, bounded to provide backpressure.
+    slots: Arc<Semaphore>,
+    /// Dropped with the last clone, which stops the workers once the queue is empty.
+    sender: Mutex<Sender<Job>>,
+}
+
+impl AsyncOsrmEngine {
+    pub fn new(engine: OsrmEngine, config: PoolConfig) -> Result<Self, OsrmError> {
+        if config.threads == 0 {
+            return Err(OsrmError::InvalidConfig("threads must be at least 1".to_owned()));
+        }
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        // Workers are detached, they exit on their own once the sender is dropped.
+        for index in 0..config.threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("osrm-worker-{}", index))
+                .spawn(move || work(&receiver))
+                .map_err(|e| OsrmError::WorkerPool(e.to_string()))?;
+        }
+
+        Ok(AsyncOsrmEngine {
+            inner: Arc::new(Inner {
+                engine: Arc::new(engine),
+                slots: Arc::new(Semaphore::new(config.threads + config.queue_capacity)),
+                sender: Mutex::new(sender),
+            }),
+        })
+    }
+
+    /// Engine used by the pool, for blocking calls.
+    pub fn engine(&self) -> &OsrmEngine {
+        &self.inner.engine
+    }
+
+    pub async fn table(&self, table_request: TableRequest) -> Result<TableResponse, OsrmError> {
+        self.run(move |engine| engine.table(table_request)).await
+    }
+
+    pub async fn route(&self, route_request: RouteRequest) -> Result<RouteResponse, OsrmError> {
+        self.run(move |engine| engine.route(route_request)).await
+    }
+
+    pub async fn trip(&self, trip_request: TripRequest) -> Result<TripResponse, OsrmError> {
+        self.run(move |engine| engine.trip(trip_request)).await
+    }
+
+    pub async fn nearest(&self, nearest_request: NearestRequest) -> Result<NearestResponse, OsrmError> {
+        self.run(move |engine| engine.nearest(nearest_request)).await
+    }
+
+    /// Runs any blocking call on the pool.
+    pub async fn run<T, F>(&self, request: F) -> Result<T, OsrmError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&OsrmEngine) -> Result<T, OsrmError> + Send + 'static,
+    {
+        let slot = self.inner.slots.clone().acquire_owned().await
+            .map_err(|_| OsrmError::WorkerPool("the pool is closed".to_owned()))?;
+        let (result_sender, result_receiver) = oneshot::channel();
+        let engine = self.inner.engine.clone();
+        let job: Job = Box::new(move || {
+            let _slot = slot;
+            // The caller dropped its future while the request was queued.
+            if result_sender.is_closed() {
+                return;
+            }
+            let _ = result_sender.send(request(&engine));
+        });
+
+        self.inner.sender.lock().unwrap_or_else(PoisonError::into_inner)
+            .send(job)
+            .map_err(|_| OsrmError::WorkerPool("the pool is closed".to_owned()))?;
+
+        result_receiver.await.map_err(|_| OsrmError::WorkerPool("the request panicked".to_owned()))?
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match receiver.lock().unwrap_or_else(PoisonError::into_inner).recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        // A panicking request only fails its own future, the worker keeps serving.
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::point::Point;
+    use crate::route::RouteRequestBuilder;
+    use crate::tables::TableRequestBuilder;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().expect("Failed to build the runtime").block_on(future)
+    }
+
+    #[test]
+    fn it_calculates_routes_and_tables_asynchronously() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+        let config = PoolConfigBuilder::default().threads(2).queue_capacity(1).build().expect("Failed to build PoolConfig");
+        let engine = AsyncOsrmEngine::new(engine, config).expect("Failed to start the pool");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        let route = RouteRequestBuilder::default().points(vec![paris.clone(), lyon.clone()]).build().expect("Failed to build RouteRequest");
+        let table = TableRequestBuilder::default().sources(vec![paris]).destinations(vec![lyon]).build().expect("Failed to build TableRequest");
+
+        let (routes, table) = block_on(async {
+            // More requests than threads and queue slots, the last ones wait for a slot.
+            let routes = (0..5)
+                .map(|_| {
+                    let engine = engine.clone();
+                    let route = route.clone();
+                    tokio::spawn(async move { engine.route(route).await })
+                })
+                .collect::<Vec<_>>();
+            let mut responses = Vec::new();
+            for route in routes {
+                responses.push(route.await.expect("The route task panicked"));
+            }
+            (responses, engine.table(table).await)
+        });
+
+        assert!(routes.iter().all(|route| route.as_ref().is_ok_and(|route| route.code == "Ok")));
+        assert!(table.expect("Table request failed").durations[0][0].is_some());
+    }
+
+    #[test]
+    fn it_skips_requests_dropped_while_queued() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+        let config = PoolConfigBuilder::default().threads(1).build().expect("Failed to build PoolConfig");
+        let engine = AsyncOsrmEngine::new(engine, config).expect("Failed to start the pool");
+        let executed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        block_on(async {
+            let (release, blocked) = std::sync::mpsc::channel::<()>();
+            // Occupies the only worker until released.
+            let busy = tokio::spawn({
+                let engine = engine.clone();
+                async move { engine.run(move |_| { blocked.recv().ok(); Ok(()) }).await }
+            });
+            tokio::task::yield_now().await;
+
+            let counter = executed.clone();
+            let queued = engine.run(move |_| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            });
+            // Polls the request once so it gets queued, then drops it.
+            let _ = poll_once(queued).await;
+
+            release.send(()).expect("The busy request should be waiting");
+            busy.await.expect("The busy task panicked").expect("The busy request failed");
+            engine.run(|_| Ok(())).await.expect("The pool should still serve requests");
+        });
+
+        assert_eq!(executed.load(std::sync::atomic::Ordering::SeqCst), 0, "The dropped request should not run");
+    }
+
+    #[test]
+    fn it_drops_the_pool_without_waiting_for_running_requests() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+        let config = PoolConfigBuilder::default().threads(1).build().expect("Failed to build PoolConfig");
+        let engine = AsyncOsrmEngine::new(engine, config).expect("Failed to start the pool");
+        let (started, running) = std::sync::mpsc::channel::<()>();
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let (finished, done) = std::sync::mpsc::channel::<()>();
+
+        block_on(async {
+            let mut request = std::pin::pin!(engine.run(move |_| {
+                started.send(()).ok();
+                blocked.recv().ok();
+                finished.send(()).ok();
+                Ok(())
+            }));
+            // Polls the request once so it gets queued, and keeps it until it runs.
+            std::future::poll_fn(|context| {
+                let _ = request.as_mut().poll(context);
+                std::task::Poll::Ready(())
+            })
+            .await;
+            running.recv().expect("The request should start");
+        });
+        // Would deadlock if dropping the pool waited for the running request.
+        drop(engine);
+
+        release.send(()).expect("The request should still be running");
+        done.recv_timeout(std::time::Duration::from_secs(10)).expect("The running request should complete after the drop");
+    }
+
+    /// Polls a future once and drops it.
+    async fn poll_once<F: std::future::Future>(future: F) -> Option<F::Output> {
+        let mut future = std::pin::pin!(future);
+        std::future::poll_fn(|context| std::task::Poll::Ready(match future.as_mut().poll(context) {
+            std::task::Poll::Ready(output) => Some(output),
+            std::task::Poll::Pending => None,
+        }))
+        .await
+    }
+}
@@ -0,0 +1,62 @@
+//! Rust equivalent of `osrm-datastore`: loads datasets into shared memory so that
+//! several processes can serve them through `EngineConfig::use_shared_memory`.
+use std::time::Duration;
+use derive_builder::Builder;
+use serde::Deserialize;
+use crate::errors::OsrmError;
+
+#[derive(Debug, Builder, Clone)]
+pub struct LoadRequest {
+    /// Path of the `.osrm` dataset.
+    #[builder(setter(into))]
+    pub path: String,
+    /// Name of the dataset, engines select it with `EngineConfig::dataset_name`.
+    #[builder(default, setter(into))]
+    pub dataset_name: String,
+    /// Only reloads the metric (weights and durations), e.g. after a traffic update.
+    #[builder(default)]
+    pub only_metric: bool,
+    /// How long to wait for engines still using the previous data, forever when `None`.
+    #[builder(default, setter(strip_option))]
+    pub max_wait: Option<Duration>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SharedRegion {
+    /// `<dataset_name>/static` or `<dataset_name>/updatable`.
+    pub name: String,
+    pub shm_key: u8,
+    pub timestamp: u64,
+    /// Size of the region in bytes.
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedRegions {
+    regions: Vec<SharedRegion>,
+}
+
+/// Loads a dataset into shared memory, atomically replacing any dataset with the same name.
+pub fn load(load_request: LoadRequest) -> Result<(), OsrmError> {
+    if load_request.path.is_empty() {
+        return Err(OsrmError::InvalidPath(load_request.path));
+    }
+    let max_wait = match load_request.max_wait {
+        None => -1,
+        Some(wait) => i32::try_from(wait.as_secs()).map_err(|_| OsrmError::Datastore("max_wait is too long".to_owned()))?,
+    };
+    crate::datastore_load(&load_request.path, &load_request.dataset_name, load_request.only_metric, max_wait)
+}
+
+/// Lists the shared memory regions currently registered.
+pub fn list() -> Result<Vec<SharedRegion>, OsrmError> {
+    let result = crate::datastore_list()?;
+    let regions = serde_json::from_str::<SharedRegions>(&result).map_err(OsrmError::JsonParse)?;
+    Ok(regions.regions)
+}
+
+/// Removes every region of a dataset. Engines still attached to it keep working
+/// until they are dropped.
+pub fn drop_dataset(dataset_name: &str) -> Result<(), OsrmError> {
+    crate::datastore_drop(dataset_name)
+}
@@ -0,0 +1,103 @@
+use std::ffi::CString;
+use derive_builder::Builder;
+use crate::algorithm::Algorithm;
+use crate::errors::OsrmError;
+use crate::OsrmEngineConfig;
+
+/// Configuration of an `OsrmEngine`. Limits left to `None` are unlimited.
+#[derive(Debug, Builder, Clone)]
+pub struct EngineConfig {
+    /// Path of the `.osrm` dataset, unused when `use_shared_memory` is set.
+    #[builder(default, setter(into))]
+    pub path: String,
+    #[builder(default = "Algorithm::MLD")]
+    pub algorithm: Algorithm,
+    #[builder(default, setter(strip_option))]
+    pub max_locations_trip: Option<usize>,
+    #[builder(default, setter(strip_option))]
+    pub max_locations_viaroute: Option<usize>,
+    #[builder(default, setter(strip_option))]
+    pub max_locations_distance_table: Option<usize>,
+    #[builder(default, setter(strip_option))]
+    pub max_locations_map_matching: Option<usize>,
+    /// Maximum `Point::radius` accepted by the match service, in meters.
+    #[builder(default, setter(strip_option))]
+    pub max_radius_map_matching: Option<f64>,
+    #[builder(default, setter(strip_option))]
+    pub max_results_nearest: Option<usize>,
+    /// OSRM allows 3 alternatives when `None`.
+    #[builder(default, setter(strip_option))]
+    pub max_alternatives: Option<usize>,
+    /// Snapping radius in meters for points without a `Point::radius`.
+    #[builder(default, setter(strip_option))]
+    pub default_radius: Option<f64>,
+    /// Maps the dataset files to memory instead of reading them, OSRM decides when `None`.
+    #[builder(default, setter(strip_option))]
+    pub use_mmap: Option<bool>,
+    /// Reads the dataset from shared memory previously filled with `datastore::load`.
+    #[builder(default)]
+    pub use_shared_memory: bool,
+    /// Name of the shared memory dataset, OSRM's unnamed dataset when `None`.
+    #[builder(default, setter(into, strip_option))]
+    pub dataset_name: Option<String>,
+}
+
+impl EngineConfig {
+    pub(crate) fn validate(&self) -> Result<(), OsrmError> {
+        if self.path.is_empty() && !self.use_shared_memory {
+            return Err(OsrmError::InvalidPath(self.path.clone()));
+        }
+        if self.dataset_name.is_some() && !self.use_shared_memory {
+            return Err(OsrmError::InvalidConfig("dataset_name requires use_shared_memory".to_owned()));
+        }
+        if self.dataset_name.as_ref().is_some_and(|name| name.contains('\0')) {
+            return Err(OsrmError::InvalidConfig("dataset_name must not contain a nul byte".to_owned()));
+        }
+        let limits = [
+            ("max_locations_trip", self.max_locations_trip),
+            ("max_locations_viaroute", self.max_locations_viaroute),
+            ("max_locations_distance_table", self.max_locations_distance_table),
+            ("max_locations_map_matching", self.max_locations_map_matching),
+            ("max_results_nearest", self.max_results_nearest),
+        ];
+        for (name, limit) in limits {
+            if limit.is_some_and(|limit| limit == 0 || limit > i32::MAX as usize) {
+                return Err(OsrmError::InvalidConfig(format!("{} must be between 1 and {}", name, i32::MAX)));
+            }
+        }
+        if self.max_alternatives.is_some_and(|max| max > i32::MAX as usize) {
+            return Err(OsrmError::InvalidConfig(format!("max_alternatives must be at most {}", i32::MAX)));
+        }
+        let radiuses = [
+            ("max_radius_map_matching", self.max_radius_map_matching),
+            ("default_radius", self.default_radius),
+        ];
+        for (name, radius) in radiuses {
+            if radius.is_some_and(|radius| !radius.is_finite() || radius <= 0.0) {
+                return Err(OsrmError::InvalidConfig(format!("{} must be a positive number of meters", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Must only be called on a validated configuration. The returned `CString`
+    /// owns the dataset name and must outlive the FFI call.
+    pub(crate) fn to_raw(&self) -> (OsrmEngineConfig, Option<CString>) {
+        let limit = |limit: Option<usize>| limit.map_or(-1, |limit| limit as i32);
+        let dataset_name = self.dataset_name.as_deref().map(|name| CString::new(name).expect("dataset_name is validated"));
+        let config = OsrmEngineConfig {
+            max_locations_trip: limit(self.max_locations_trip),
+            max_locations_viaroute: limit(self.max_locations_viaroute),
+            max_locations_distance_table: limit(self.max_locations_distance_table),
+            max_locations_map_matching: limit(self.max_locations_map_matching),
+            max_radius_map_matching: self.max_radius_map_matching.unwrap_or(-1.0),
+            max_results_nearest: limit(self.max_results_nearest),
+            max_alternatives: limit(self.max_alternatives),
+            default_radius: self.default_radius.unwrap_or(-1.0),
+            use_mmap: self.use_mmap.map_or(-1, i32::from),
+            use_shared_memory: self.use_shared_memory,
+            dataset_name: dataset_name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr()),
+        };
+        (config, dataset_name)
+    }
+}
@@ -1,17 +1,84 @@
 use thiserror::Error;
+use crate::pipeline::PipelineStage;
 
 #[derive(Error, Debug)]
 pub enum OsrmError {
-    #[error("Failed to create OSRM instance")]
-    Initialization,
+    #[error("Failed to create OSRM instance: {0}")]
+    Initialization(String),
     #[error("Invalid path parameter: {0}")]
     InvalidPath(String),
+    #[error("Invalid engine configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
     #[error("OSRM API error: {0}")]
     ApiError(String),
     #[error("Sources or destinations are invalid")]
     InvalidTableArgument,
     #[error("Failed to parse OSRM response: {0}")]
     JsonParse(#[from] serde_json::Error),
+    #[error("Shared memory datastore error: {0}")]
+    Datastore(String),
+    #[error("OSRM {stage} failed: {message}")]
+    Pipeline { stage: PipelineStage, message: String },
+    #[error("Worker pool error: {0}")]
+    WorkerPool(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
     #[error("Internal FFI error: {0}")]
     FfiError(String),
+    #[error("No route found: {0}")]
+    NoRoute(String),
+    #[error("No segment found for a coordinate: {0}")]
+    NoSegment(String),
+    #[error("No table found: {0}")]
+    NoTable(String),
+    #[error("No trip found: {0}")]
+    NoTrips(String),
+    #[error("No matching found: {0}")]
+    NoMatch(String),
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("Invalid value: {0}")]
+    InvalidValue(String),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Invalid service: {0}")]
+    InvalidService(String),
+    #[error("Invalid version: {0}")]
+    InvalidVersion(String),
+    #[error("Request too big: {0}")]
+    TooBig(String),
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
+    #[error("Dataset disabled: {0}")]
+    DisabledDataset(String),
+    #[error("OSRM raised an exception: {0}")]
+    Exception(String),
+}
+
+impl OsrmError {
+    /// Maps the status code returned by the wrapper, see `error_code` in `wrapper.cpp`.
+    pub(crate) fn from_code(code: i32, message: String) -> Self {
+        match code {
+            2 => OsrmError::NoRoute(message),
+            3 => OsrmError::NoSegment(message),
+            4 => OsrmError::NoTable(message),
+            5 => OsrmError::NoTrips(message),
+            6 => OsrmError::NoMatch(message),
+            7 => OsrmError::InvalidOptions(message),
+            8 => OsrmError::InvalidQuery(message),
+            9 => OsrmError::InvalidValue(message),
+            10 => OsrmError::InvalidUrl(message),
+            11 => OsrmError::InvalidService(message),
+            12 => OsrmError::InvalidVersion(message),
+            13 => OsrmError::TooBig(message),
+            14 => OsrmError::NotImplemented(message),
+            15 => OsrmError::DisabledDataset(message),
+            16 => OsrmError::Exception(message),
+            _ => OsrmError::FfiError(message),
+        }
+    }
 }
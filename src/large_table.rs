@@ -0,0 +1,199 @@
+//! Matrices too large for a single table call, computed block by block and stitched together.
+use derive_builder::Builder;
+use crate::errors::OsrmError;
+use crate::point::Point;
+use crate::tables::{self, FallbackCoordinate, TableAnnotation};
+use crate::OsrmTableOptions;
+
+#[derive(Debug, Builder, Clone)]
+pub struct LargeTableRequest {
+    pub sources: Vec<Point>,
+    pub destinations: Vec<Point>,
+    /// Matrices to compute, durations only by default.
+    #[builder(default = "vec![TableAnnotation::Duration]")]
+    pub annotations: Vec<TableAnnotation>,
+    /// Sources per block. A block sends `source_block_size + destination_block_size`
+    /// coordinates, which must fit `EngineConfig::max_locations_distance_table`.
+    #[builder(default = "500")]
+    pub source_block_size: usize,
+    /// Destinations per block.
+    #[builder(default = "500")]
+    pub destination_block_size: usize,
+    /// Blocks computed in parallel.
+    #[builder(default = "1")]
+    pub threads: usize,
+    /// Speed in km/h used to estimate the cells for which no route was found.
+    #[builder(default, setter(strip_option))]
+    pub fallback_speed: Option<f64>,
+    /// Coordinates used for the crow-fly estimate of `fallback_speed`.
+    #[builder(default)]
+    pub fallback_coordinate: FallbackCoordinate,
+    /// Multiplies every duration of the matrix.
+    #[builder(default = "1.0")]
+    pub scale_factor: f64,
+}
+
+impl LargeTableRequest {
+    /// Also rejects blocks of more than `max_locations` coordinates, the engine's
+    /// `max_locations_distance_table`, so no block is sent only to fail.
+    pub(crate) fn validate(&self, max_locations: Option<usize>) -> Result<(), OsrmError> {
+        if self.sources.is_empty() || self.destinations.is_empty() || self.annotations.is_empty() {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        if self.fallback_speed.is_some_and(|speed| speed <= 0.0) || self.scale_factor <= 0.0 {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        if self.source_block_size == 0 || self.destination_block_size == 0 || self.threads == 0 {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        let block_locations = self.source_block_size.min(self.sources.len()) + self.destination_block_size.min(self.destinations.len());
+        if max_locations.is_some_and(|max_locations| block_locations > max_locations) {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn options(&self) -> OsrmTableOptions {
+        tables::table_options(&self.annotations, self.fallback_speed, self.fallback_coordinate, self.scale_factor)
+    }
+
+    /// Source and destination ranges of every block, row of blocks by row of blocks.
+    pub(crate) fn blocks(&self) -> Vec<TableBlock> {
+        let ranges = |len: usize, size: usize| (0..len).step_by(size).map(move |start| (start, (start + size).min(len)));
+        ranges(self.sources.len(), self.source_block_size)
+            .flat_map(|sources| {
+                ranges(self.destinations.len(), self.destination_block_size)
+                    .map(move |destinations| TableBlock { sources, destinations })
+            })
+            .collect()
+    }
+}
+
+/// Half-open `(start, end)` ranges of the sources and destinations of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TableBlock {
+    pub(crate) sources: (usize, usize),
+    pub(crate) destinations: (usize, usize),
+}
+
+/// Reported after each computed block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableProgress {
+    pub completed_blocks: usize,
+    pub total_blocks: usize,
+}
+
+/// Dense matrices in row-major order, one row per source. Cells without a route are NaN.
+#[derive(Debug, Clone, Default)]
+pub struct LargeTableResponse {
+    pub rows: usize,
+    pub columns: usize,
+    /// Seconds, only present when `TableAnnotation::Duration` was requested.
+    pub durations: Option<Vec<f32>>,
+    /// Meters, only present when `TableAnnotation::Distance` was requested.
+    pub distances: Option<Vec<f32>>,
+    /// `[row, column]` of the cells estimated with `fallback_speed` instead of routed.
+    pub fallback_speed_cells: Vec<[usize; 2]>,
+}
+
+impl LargeTableResponse {
+    pub(crate) fn new(rows: usize, columns: usize, annotations: &[TableAnnotation]) -> Self {
+        let matrix = |annotation| annotations.contains(&annotation).then(|| vec![f32::NAN; rows * columns]);
+        LargeTableResponse {
+            rows,
+            columns,
+            durations: matrix(TableAnnotation::Duration),
+            distances: matrix(TableAnnotation::Distance),
+            fallback_speed_cells: Vec::new(),
+        }
+    }
+
+    /// Copies the row-major values of a block at its position in the matrix.
+    pub(crate) fn stitch(&mut self, block: TableBlock, durations: Option<&[f64]>, distances: Option<&[f64]>, fallback_speed_cells: &[[usize; 2]]) {
+        let (row, column) = (block.sources.0, block.destinations.0);
+        let width = block.destinations.1 - column;
+        let columns = self.columns;
+        let copy = |target: &mut Option<Vec<f32>>, values: Option<&[f64]>| {
+            let (Some(target), Some(values)) = (target.as_mut(), values) else { return };
+            for (index, values) in values.chunks(width).enumerate() {
+                let start = (row + index) * columns + column;
+                for (cell, value) in target[start..start + width].iter_mut().zip(values) {
+                    *cell = *value as f32;
+                }
+            }
+        };
+        copy(&mut self.durations, durations);
+        copy(&mut self.distances, distances);
+        self.fallback_speed_cells.extend(fallback_speed_cells.iter().map(|cell| [row + cell[0], column + cell[1]]));
+    }
+
+    pub fn duration(&self, row: usize, column: usize) -> Option<f32> {
+        self.cell(self.durations.as_deref(), row, column)
+    }
+
+    pub fn distance(&self, row: usize, column: usize) -> Option<f32> {
+        self.cell(self.distances.as_deref(), row, column)
+    }
+
+    fn cell(&self, values: Option<&[f32]>, row: usize, column: usize) -> Option<f32> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+        values.map(|values| values[row * self.columns + column]).filter(|value| !value.is_nan())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(len: usize) -> Vec<Point> {
+        (0..len).map(|index| Point { longitude: index as f64, ..Default::default() }).collect()
+    }
+
+    #[test]
+    fn it_tiles_the_matrix_into_blocks() {
+        let request = LargeTableRequestBuilder::default()
+            .sources(points(5))
+            .destinations(points(3))
+            .source_block_size(2usize)
+            .destination_block_size(2usize)
+            .build()
+            .expect("Failed to build LargeTableRequest");
+
+        let blocks = request.blocks();
+        assert_eq!(blocks.len(), 6);
+        assert_eq!(blocks[0], TableBlock { sources: (0, 2), destinations: (0, 2) });
+        assert_eq!(blocks[1], TableBlock { sources: (0, 2), destinations: (2, 3) });
+        assert_eq!(blocks[5], TableBlock { sources: (4, 5), destinations: (2, 3) });
+    }
+
+    #[test]
+    fn it_rejects_blocks_above_the_table_limit() {
+        let request = LargeTableRequestBuilder::default()
+            .sources(points(5))
+            .destinations(points(3))
+            .source_block_size(2usize)
+            .destination_block_size(2usize)
+            .build()
+            .expect("Failed to build LargeTableRequest");
+
+        assert!(request.validate(None).is_ok());
+        assert!(request.validate(Some(4)).is_ok());
+        assert!(matches!(request.validate(Some(3)), Err(OsrmError::InvalidTableArgument)), "Blocks of 4 locations should exceed the limit");
+    }
+
+    #[test]
+    fn it_stitches_blocks_in_row_major_order() {
+        let mut response = LargeTableResponse::new(3, 3, &[TableAnnotation::Duration]);
+        response.stitch(TableBlock { sources: (0, 2), destinations: (1, 3) }, Some(&[1.0, 2.0, 3.0, f64::NAN]), None, &[[1, 1]]);
+
+        assert_eq!(response.duration(0, 1), Some(1.0));
+        assert_eq!(response.duration(0, 2), Some(2.0));
+        assert_eq!(response.duration(1, 1), Some(3.0));
+        assert_eq!(response.duration(1, 2), None, "Unreachable cells should stay NaN");
+        assert_eq!(response.duration(0, 0), None, "Cells outside of the block should be untouched");
+        assert_eq!(response.distance(0, 1), None, "Distances were not requested");
+        assert_eq!(response.fallback_speed_cells, vec![[1, 2]]);
+    }
+}
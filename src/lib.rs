@@ -6,9 +6,25 @@ pub mod point;
 pub mod route;
 pub mod waypoints;
 pub mod osrm_engine;
+pub mod options;
+pub mod nearest;
+pub mod matching;
+pub mod tile;
+pub mod engine_config;
+pub mod datastore;
+pub mod pipeline;
+pub mod traffic;
+pub mod reloadable;
+pub mod registry;
+pub mod large_table;
+#[cfg(feature = "async")]
+pub mod async_engine;
 // src/lib.rs
 use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
+use crate::errors::OsrmError;
+use crate::pipeline::{PipelineConfig, PipelineStage};
+use crate::point::Point;
 
 #[repr(C)]
 struct OsrmResult {
@@ -16,32 +32,411 @@ struct OsrmResult {
     message: *mut c_char,
 }
 
+#[repr(C)]
+struct OsrmBuffer {
+    code: i32,
+    data: *mut u8,
+    length: usize,
+}
+
+#[repr(C)]
+struct OsrmCoordinate {
+    longitude: f64,
+    latitude: f64,
+    has_bearing: bool,
+    bearing: i16,
+    bearing_range: i16,
+    has_radius: bool,
+    radius: f64,
+    hint: *const c_char,
+    approach: i32,
+}
+
+/// Negative values keep OSRM's default.
+#[repr(C)]
+pub(crate) struct OsrmEngineConfig {
+    pub(crate) max_locations_trip: i32,
+    pub(crate) max_locations_viaroute: i32,
+    pub(crate) max_locations_distance_table: i32,
+    pub(crate) max_locations_map_matching: i32,
+    pub(crate) max_radius_map_matching: f64,
+    pub(crate) max_results_nearest: i32,
+    pub(crate) max_alternatives: i32,
+    pub(crate) default_radius: f64,
+    pub(crate) use_mmap: i32,
+    pub(crate) use_shared_memory: bool,
+    pub(crate) dataset_name: *const c_char,
+}
+
+#[repr(C)]
+pub(crate) struct OsrmTableOptions {
+    pub(crate) annotations: u32,
+    pub(crate) has_fallback_speed: bool,
+    pub(crate) fallback_speed: f64,
+    pub(crate) fallback_coordinate: i32,
+    pub(crate) scale_factor: f64,
+}
+
+#[repr(C)]
+struct OsrmExtractConfig {
+    input_path: *const c_char,
+    profile_path: *const c_char,
+    output_path: *const c_char,
+    threads: i32,
+}
+
+#[repr(C)]
+struct OsrmPipelineConfig {
+    base_path: *const c_char,
+    threads: i32,
+    segment_speed_files: *const *const c_char,
+    num_segment_speed_files: usize,
+    turn_penalty_files: *const *const c_char,
+    num_turn_penalty_files: usize,
+}
+
+#[repr(C)]
+struct OsrmTableWaypoint {
+    longitude: f64,
+    latitude: f64,
+    distance: f64,
+    name: *mut c_char,
+    hint: *mut c_char,
+}
+
+/// Buffers filled by `osrm_table`, matrices are row-major and null when not requested.
+#[repr(C)]
+struct OsrmTableBuffers {
+    durations: *mut f64,
+    distances: *mut f64,
+    sources: *mut OsrmTableWaypoint,
+    destinations: *mut OsrmTableWaypoint,
+    fallback_speed_cells: *mut usize,
+    num_fallback_speed_cells: usize,
+}
+
+/// Source or destination of a table result.
+pub(crate) struct TableWaypoint {
+    pub(crate) location: [f64; 2],
+    pub(crate) distance: f64,
+    pub(crate) name: String,
+    pub(crate) hint: String,
+}
+
+/// Table result copied out of OSRM's flatbuffers result, without building any JSON.
+pub(crate) struct TableResult {
+    pub(crate) rows: usize,
+    pub(crate) columns: usize,
+    /// Row-major, NaN for unreachable cells.
+    pub(crate) durations: Option<Vec<f64>>,
+    pub(crate) distances: Option<Vec<f64>>,
+    pub(crate) sources: Vec<TableWaypoint>,
+    pub(crate) destinations: Vec<TableWaypoint>,
+    pub(crate) fallback_speed_cells: Vec<[usize; 2]>,
+}
+
+#[repr(C)]
+pub(crate) struct OsrmRouteOptions {
+    pub(crate) steps: bool,
+    pub(crate) alternatives: u32,
+    pub(crate) annotations: u32,
+    pub(crate) overview: i32,
+    pub(crate) geometries: i32,
+    pub(crate) continue_straight: i32,
+}
+
 #[link(name = "osrm_wrapper", kind = "static")]
 unsafe extern "C" {
-    fn osrm_create(base_path: *const c_char, algorithm : *const c_char) -> *mut c_void;
+    fn osrm_create(
+        base_path: *const c_char,
+        algorithm : *const c_char,
+        config: *const OsrmEngineConfig,
+        error: *mut *mut c_char
+    ) -> *mut c_void;
     fn osrm_destroy(osrm_instance: *mut c_void);
     fn osrm_table(
         osrm_instance: *mut c_void,
-        coordinates: *const f64,
+        coordinates: *const OsrmCoordinate,
         num_coordinates: usize,
         sources: *const usize,
         num_sources: usize,
         destinations: *const usize,
         num_destinations: usize,
+        options: *const OsrmTableOptions,
+        buffers: *mut OsrmTableBuffers,
     ) -> OsrmResult;
 
     fn osrm_trip(
         osrm_instance: *mut c_void,
-        coordinates: *const f64,
-        num_coordinates: usize
+        coordinates: *const OsrmCoordinate,
+        num_coordinates: usize,
+        roundtrip: bool,
+        source: i32,
+        destination: i32,
+        options: *const OsrmRouteOptions
     ) -> OsrmResult;
 
     fn osrm_route(
         osrm_instance: *mut c_void,
-        coordinates: *const f64,
-        num_coordinates: usize
+        coordinates: *const OsrmCoordinate,
+        num_coordinates: usize,
+        options: *const OsrmRouteOptions
+    ) -> OsrmResult;
+    fn osrm_nearest(
+        osrm_instance: *mut c_void,
+        coordinate: *const OsrmCoordinate,
+        number_of_results: u32
     ) -> OsrmResult;
+    fn osrm_match(
+        osrm_instance: *mut c_void,
+        coordinates: *const OsrmCoordinate,
+        num_coordinates: usize,
+        timestamps: *const u32,
+        num_timestamps: usize,
+        waypoints: *const usize,
+        num_waypoints: usize,
+        gaps: i32,
+        tidy: bool,
+        options: *const OsrmRouteOptions
+    ) -> OsrmResult;
+    fn osrm_tile(
+        osrm_instance: *mut c_void,
+        x: u32,
+        y: u32,
+        z: u32
+    ) -> OsrmBuffer;
+    fn osrm_datastore_load(
+        base_path: *const c_char,
+        dataset_name: *const c_char,
+        only_metric: bool,
+        max_wait: i32
+    ) -> OsrmResult;
+    fn osrm_datastore_list() -> OsrmResult;
+    fn osrm_datastore_drop(dataset_name: *const c_char) -> OsrmResult;
+    fn osrm_extract(config: *const OsrmExtractConfig) -> OsrmResult;
+    fn osrm_partition(config: *const OsrmPipelineConfig) -> OsrmResult;
+    fn osrm_customize(config: *const OsrmPipelineConfig) -> OsrmResult;
+    fn osrm_contract(config: *const OsrmPipelineConfig) -> OsrmResult;
     fn osrm_free_string(s: *mut c_char);
+    fn osrm_free_buffer(data: *mut u8);
+    fn osrm_free_indices(indices: *mut usize);
+}
+
+/// C representation of points, reused across calls to avoid reallocating. Owns the hints
+/// referenced by the coordinates.
+#[derive(Default)]
+pub(crate) struct CoordinateBuffer {
+    coordinates: Vec<OsrmCoordinate>,
+    hints: Vec<CString>,
+}
+
+impl CoordinateBuffer {
+    fn fill(&mut self, points: &[Point]) -> Result<(), OsrmError> {
+        self.coordinates.clear();
+        self.hints.clear();
+        for point in points {
+            let hint = match &point.hint {
+                Some(hint) => {
+                    let c_hint = CString::new(hint.as_str()).map_err(|e| OsrmError::FfiError(e.to_string()))?;
+                    let ptr = c_hint.as_ptr();
+                    self.hints.push(c_hint);
+                    ptr
+                }
+                None => std::ptr::null(),
+            };
+            self.coordinates.push(OsrmCoordinate {
+                longitude: point.longitude,
+                latitude: point.latitude,
+                has_bearing: point.bearing.is_some(),
+                bearing: point.bearing.map_or(0, |b| b.value as i16),
+                bearing_range: point.bearing.map_or(0, |b| b.range as i16),
+                has_radius: point.radius.is_some(),
+                radius: point.radius.unwrap_or(0.0),
+                hint,
+                approach: point.approach.map_or(-1, |a| a.as_raw()),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Converts points to their C representation. The returned `CString`s own the
+/// hints referenced by the coordinates and must outlive the FFI call.
+fn to_coordinates(points: &[Point]) -> Result<(Vec<OsrmCoordinate>, Vec<CString>), OsrmError> {
+    let mut buffer = CoordinateBuffer::default();
+    buffer.fill(points)?;
+    Ok((buffer.coordinates, buffer.hints))
+}
+
+/// Takes ownership of the message allocated by the wrapper.
+fn into_message(result: OsrmResult) -> Result<String, OsrmError> {
+    let message_ptr = result.message;
+    if message_ptr.is_null() {
+        return Err(OsrmError::FfiError("OSRM returned a null message".to_string()));
+    }
+
+    let c_str = unsafe { CStr::from_ptr(message_ptr) };
+    let rust_str = c_str.to_str().map_err(|e| OsrmError::FfiError(e.to_string())).map(|s| s.to_owned());
+
+    unsafe {
+        osrm_free_string(message_ptr);
+    }
+
+    let rust_str = rust_str?;
+    if result.code != 0 {
+        return Err(OsrmError::from_code(result.code, rust_str));
+    }
+
+    Ok(rust_str)
+}
+
+/// Takes ownership of the buffer allocated by the wrapper. On error the buffer holds the message.
+fn into_bytes(buffer: OsrmBuffer) -> Result<Vec<u8>, OsrmError> {
+    if buffer.data.is_null() {
+        return Err(OsrmError::FfiError("OSRM returned a null buffer".to_string()));
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.length) }.to_vec();
+
+    unsafe {
+        osrm_free_buffer(buffer.data);
+    }
+
+    if buffer.code != 0 {
+        return Err(OsrmError::from_code(buffer.code, String::from_utf8_lossy(&bytes).into_owned()));
+    }
+
+    Ok(bytes)
+}
+
+/// Takes ownership of a string allocated by the wrapper, null becomes an empty string.
+fn take_string(ptr: *mut c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+
+    let value = unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned();
+
+    unsafe {
+        osrm_free_string(ptr);
+    }
+
+    value
+}
+
+fn into_table_waypoints(waypoints: Vec<OsrmTableWaypoint>) -> Vec<TableWaypoint> {
+    waypoints
+        .into_iter()
+        .map(|waypoint| TableWaypoint {
+            location: [waypoint.longitude, waypoint.latitude],
+            distance: waypoint.distance,
+            name: take_string(waypoint.name),
+            hint: take_string(waypoint.hint),
+        })
+        .collect()
+}
+
+fn empty_table_waypoints(len: usize) -> Vec<OsrmTableWaypoint> {
+    (0..len)
+        .map(|_| OsrmTableWaypoint {
+            longitude: 0.0,
+            latitude: 0.0,
+            distance: 0.0,
+            name: std::ptr::null_mut(),
+            hint: std::ptr::null_mut(),
+        })
+        .collect()
+}
+
+fn datastore_error(error: OsrmError) -> OsrmError {
+    match error {
+        OsrmError::FfiError(message) => OsrmError::Datastore(message),
+        error => error,
+    }
+}
+
+pub(crate) fn datastore_load(base_path: &str, dataset_name: &str, only_metric: bool, max_wait: i32) -> Result<(), OsrmError> {
+    let c_path = CString::new(base_path).map_err(|_| OsrmError::InvalidPath(base_path.to_owned()))?;
+    let c_name = CString::new(dataset_name).map_err(|e| OsrmError::Datastore(e.to_string()))?;
+    let result = unsafe { osrm_datastore_load(c_path.as_ptr(), c_name.as_ptr(), only_metric, max_wait) };
+    into_message(result).map(|_| ()).map_err(datastore_error)
+}
+
+pub(crate) fn datastore_list() -> Result<String, OsrmError> {
+    into_message(unsafe { osrm_datastore_list() }).map_err(datastore_error)
+}
+
+pub(crate) fn datastore_drop(dataset_name: &str) -> Result<(), OsrmError> {
+    let c_name = CString::new(dataset_name).map_err(|e| OsrmError::Datastore(e.to_string()))?;
+    let result = unsafe { osrm_datastore_drop(c_name.as_ptr()) };
+    into_message(result).map(|_| ()).map_err(datastore_error)
+}
+
+fn pipeline_error(stage: PipelineStage, error: OsrmError) -> OsrmError {
+    match error {
+        OsrmError::Exception(message) | OsrmError::FfiError(message) => OsrmError::Pipeline { stage, message },
+        error => error,
+    }
+}
+
+pub(crate) fn pipeline_extract(input_path: &str, profile_path: &str, output_path: Option<&str>, threads: i32) -> Result<(), OsrmError> {
+    let c_input = CString::new(input_path).map_err(|_| OsrmError::InvalidPath(input_path.to_owned()))?;
+    let c_profile = CString::new(profile_path).map_err(|_| OsrmError::InvalidPath(profile_path.to_owned()))?;
+    let c_output = output_path
+        .map(|path| CString::new(path).map_err(|_| OsrmError::InvalidPath(path.to_owned())))
+        .transpose()?;
+    let config = OsrmExtractConfig {
+        input_path: c_input.as_ptr(),
+        profile_path: c_profile.as_ptr(),
+        output_path: c_output.as_ref().map_or(std::ptr::null(), |path| path.as_ptr()),
+        threads,
+    };
+    let result = unsafe { osrm_extract(&config) };
+    into_message(result).map(|_| ()).map_err(|e| pipeline_error(PipelineStage::Extract, e))
+}
+
+fn to_c_paths(paths: &[String]) -> Result<Vec<CString>, OsrmError> {
+    paths
+        .iter()
+        .map(|path| CString::new(path.as_str()).map_err(|_| OsrmError::InvalidPath(path.clone())))
+        .collect()
+}
+
+/// Runs one of the stages working on an extracted `.osrm` dataset.
+fn pipeline_run(
+    stage: PipelineStage,
+    run: unsafe extern "C" fn(*const OsrmPipelineConfig) -> OsrmResult,
+    config: &PipelineConfig,
+    threads: i32,
+) -> Result<(), OsrmError> {
+    let c_path = CString::new(config.path.as_str()).map_err(|_| OsrmError::InvalidPath(config.path.clone()))?;
+    let segment_speed_files = to_c_paths(&config.segment_speed_files)?;
+    let turn_penalty_files = to_c_paths(&config.turn_penalty_files)?;
+    let segment_speed_ptrs: Vec<*const c_char> = segment_speed_files.iter().map(|path| path.as_ptr()).collect();
+    let turn_penalty_ptrs: Vec<*const c_char> = turn_penalty_files.iter().map(|path| path.as_ptr()).collect();
+
+    let raw_config = OsrmPipelineConfig {
+        base_path: c_path.as_ptr(),
+        threads,
+        segment_speed_files: segment_speed_ptrs.as_ptr(),
+        num_segment_speed_files: segment_speed_ptrs.len(),
+        turn_penalty_files: turn_penalty_ptrs.as_ptr(),
+        num_turn_penalty_files: turn_penalty_ptrs.len(),
+    };
+    let result = unsafe { run(&raw_config) };
+    into_message(result).map(|_| ()).map_err(|e| pipeline_error(stage, e))
+}
+
+pub(crate) fn pipeline_partition(config: &PipelineConfig, threads: i32) -> Result<(), OsrmError> {
+    pipeline_run(PipelineStage::Partition, osrm_partition, config, threads)
+}
+
+pub(crate) fn pipeline_customize(config: &PipelineConfig, threads: i32) -> Result<(), OsrmError> {
+    pipeline_run(PipelineStage::Customize, osrm_customize, config, threads)
+}
+
+pub(crate) fn pipeline_contract(config: &PipelineConfig, threads: i32) -> Result<(), OsrmError> {
+    pipeline_run(PipelineStage::Contract, osrm_contract, config, threads)
 }
 
 pub(crate) struct Osrm {
@@ -49,110 +444,187 @@ pub(crate) struct Osrm {
 }
 
 impl Osrm {
-    pub(crate) fn new(base_path: &str, algorithm: &str) -> Result<Self, String> {
-        let c_path = CString::new(base_path).map_err(|e| e.to_string())?;
-        let c_algorithm = CString::new(algorithm).map_err(|e| e.to_string())?;
-        let instance = unsafe { osrm_create(c_path.as_ptr(), c_algorithm.as_ptr()) };
+    pub(crate) fn new(base_path: &str, algorithm: &str, config: &OsrmEngineConfig) -> Result<Self, OsrmError> {
+        let c_path = CString::new(base_path).map_err(|_| OsrmError::InvalidPath(base_path.to_owned()))?;
+        let c_algorithm = CString::new(algorithm).map_err(|e| OsrmError::FfiError(e.to_string()))?;
+        let mut error: *mut c_char = std::ptr::null_mut();
+        let instance = unsafe { osrm_create(c_path.as_ptr(), c_algorithm.as_ptr(), config, &mut error) };
 
         if instance.is_null() {
-            Err("Failure to create an OSRM instance.".to_string())
+            let message = if error.is_null() {
+                "Failure to create an OSRM instance.".to_string()
+            } else {
+                let message = unsafe { CStr::from_ptr(error) }.to_string_lossy().into_owned();
+                unsafe {
+                    osrm_free_string(error);
+                }
+                message
+            };
+            Err(OsrmError::Initialization(message))
         } else {
             Ok(Osrm { instance })
         }
     }
 
-    pub(crate) fn trip(&self, coordinates: &[(f64, f64)]) -> Result<String, String> {
+    pub(crate) fn trip(
+        &self,
+        points: &[Point],
+        roundtrip: bool,
+        source: i32,
+        destination: i32,
+        options: &OsrmRouteOptions,
+    ) -> Result<String, OsrmError> {
 
-        let coords : Vec<f64> = coordinates.iter().flat_map(|&(lon, lat)| vec![lon, lat]).collect();
+        let (coordinates, _hints) = to_coordinates(points)?;
         let result = unsafe {
-            osrm_trip(self.instance, coords.as_ptr(), coordinates.len() )
+            osrm_trip(self.instance, coordinates.as_ptr(), coordinates.len(), roundtrip, source, destination, options)
         };
 
-        let message_ptr = result.message;
-        if message_ptr.is_null() {
-            return Err("OSRM returned a null message".to_string());
-        }
-
-        let c_str = unsafe { CStr::from_ptr(message_ptr) };
-        let rust_str = c_str.to_str().map_err(|e| e.to_string())?.to_owned();
+        into_message(result)
+    }
 
-        unsafe {
-            osrm_free_string(message_ptr);
-        }
+    pub(crate) fn route_with(
+        &self,
+        buffer: &mut CoordinateBuffer,
+        points: &[Point],
+        options: &OsrmRouteOptions,
+    ) -> Result<String, OsrmError> {
 
-        if result.code != 0 {
-            return Err(format!("OSRM error: {}", rust_str));
-        }
+        buffer.fill(points)?;
+        let result = unsafe {
+            osrm_route(self.instance, buffer.coordinates.as_ptr(), buffer.coordinates.len(), options)
+        };
 
-        Ok(rust_str)
+        into_message(result)
     }
 
-    pub(crate) fn route(&self, coordinates: &[(f64, f64)]) -> Result<String, String> {
+    pub(crate) fn nearest(&self, point: &Point, number_of_results: u32) -> Result<String, OsrmError> {
 
-        let coords : Vec<f64> = coordinates.iter().flat_map(|&(lon, lat)| vec![lon, lat]).collect();
+        let (coordinates, _hints) = to_coordinates(std::slice::from_ref(point))?;
         let result = unsafe {
-            osrm_route(self.instance, coords.as_ptr(), coordinates.len() )
+            osrm_nearest(self.instance, coordinates.as_ptr(), number_of_results)
         };
 
-        let message_ptr = result.message;
-        if message_ptr.is_null() {
-            return Err("OSRM returned a null message".to_string());
-        }
+        into_message(result)
+    }
 
-        let c_str = unsafe { CStr::from_ptr(message_ptr) };
-        let rust_str = c_str.to_str().map_err(|e| e.to_string())?.to_owned();
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn matching(
+        &self,
+        points: &[Point],
+        timestamps: &[u32],
+        waypoints: Option<&[usize]>,
+        gaps: i32,
+        tidy: bool,
+        options: &OsrmRouteOptions,
+    ) -> Result<String, OsrmError> {
 
-        unsafe {
-            osrm_free_string(message_ptr);
-        }
+        let (coordinates, _hints) = to_coordinates(points)?;
+        let waypoints = waypoints.unwrap_or(&[]);
+        let result = unsafe {
+            osrm_match(
+                self.instance,
+                coordinates.as_ptr(),
+                coordinates.len(),
+                timestamps.as_ptr(),
+                timestamps.len(),
+                waypoints.as_ptr(),
+                waypoints.len(),
+                gaps,
+                tidy,
+                options,
+            )
+        };
 
-        if result.code != 0 {
-            return Err(format!("OSRM error: {}", rust_str));
-        }
+        into_message(result)
+    }
 
-        Ok(rust_str)
+    pub(crate) fn tile(&self, x: u32, y: u32, z: u32) -> Result<Vec<u8>, OsrmError> {
+        let result = unsafe { osrm_tile(self.instance, x, y, z) };
+        into_bytes(result)
     }
 
     pub(crate) fn table(
         &self,
-        coordinates: &[(f64, f64)],
+        points: &[Point],
         sources: Option<&[usize]>,
         destinations: Option<&[usize]>,
-    ) -> Result<String, String> {
+        options: &OsrmTableOptions,
+    ) -> Result<TableResult, OsrmError> {
+        self.table_with(&mut CoordinateBuffer::default(), points, sources, destinations, options)
+    }
 
-        let flat_coords: Vec<f64> = coordinates.iter().flat_map(|&(lon, lat)| vec![lon, lat]).collect();
+    pub(crate) fn table_with(
+        &self,
+        buffer: &mut CoordinateBuffer,
+        points: &[Point],
+        sources: Option<&[usize]>,
+        destinations: Option<&[usize]>,
+        options: &OsrmTableOptions,
+    ) -> Result<TableResult, OsrmError> {
+
+        buffer.fill(points)?;
         let sources_vec = sources.unwrap_or(&[]).to_vec();
         let dests_vec = destinations.unwrap_or(&[]).to_vec();
 
+        let rows = if sources_vec.is_empty() { points.len() } else { sources_vec.len() };
+        let columns = if dests_vec.is_empty() { points.len() } else { dests_vec.len() };
+        // OSRM computes durations only when no annotation is set.
+        let wants_durations = options.annotations == 0 || options.annotations & 0x01 != 0;
+        let wants_distances = options.annotations & 0x02 != 0;
+
+        let mut durations = if wants_durations { vec![f64::NAN; rows * columns] } else { Vec::new() };
+        let mut distances = if wants_distances { vec![f64::NAN; rows * columns] } else { Vec::new() };
+        let mut source_waypoints = empty_table_waypoints(rows);
+        let mut destination_waypoints = empty_table_waypoints(columns);
+        let mut buffers = OsrmTableBuffers {
+            durations: if wants_durations { durations.as_mut_ptr() } else { std::ptr::null_mut() },
+            distances: if wants_distances { distances.as_mut_ptr() } else { std::ptr::null_mut() },
+            sources: source_waypoints.as_mut_ptr(),
+            destinations: destination_waypoints.as_mut_ptr(),
+            fallback_speed_cells: std::ptr::null_mut(),
+            num_fallback_speed_cells: 0,
+        };
+
         let result = unsafe {
             osrm_table(
                 self.instance,
-                flat_coords.as_ptr(),
-                coordinates.len(),
+                buffer.coordinates.as_ptr(),
+                buffer.coordinates.len(),
                 sources_vec.as_ptr(),
                 sources_vec.len(),
                 dests_vec.as_ptr(),
                 dests_vec.len(),
+                options,
+                &mut buffers,
             )
         };
 
-        let message_ptr = result.message;
-        if message_ptr.is_null() {
-            return Err("OSRM returned a null message".to_string());
-        }
-
-        let c_str = unsafe { CStr::from_ptr(message_ptr) };
-        let rust_str = c_str.to_str().map_err(|e| e.to_string())?.to_owned();
-
-        unsafe {
-            osrm_free_string(message_ptr);
+        // Release everything the wrapper allocated before looking at the status.
+        let sources = into_table_waypoints(source_waypoints);
+        let destinations = into_table_waypoints(destination_waypoints);
+        let mut fallback_speed_cells = Vec::new();
+        if !buffers.fallback_speed_cells.is_null() {
+            let cells = unsafe {
+                std::slice::from_raw_parts(buffers.fallback_speed_cells, buffers.num_fallback_speed_cells * 2)
+            };
+            fallback_speed_cells = cells.chunks_exact(2).map(|cell| [cell[0], cell[1]]).collect();
+            unsafe {
+                osrm_free_indices(buffers.fallback_speed_cells);
+            }
         }
 
-        if result.code != 0 {
-            return Err(format!("OSRM error: {}", rust_str));
-        }
+        into_message(result)?;
 
-        Ok(rust_str)
+        Ok(TableResult {
+            rows,
+            columns,
+            durations: wants_durations.then_some(durations),
+            distances: wants_distances.then_some(distances),
+            sources,
+            destinations,
+            fallback_speed_cells,
+        })
     }
 }
 
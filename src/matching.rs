@@ -0,0 +1,95 @@
+use derive_builder::Builder;
+use serde::Deserialize;
+use crate::options::{annotations_mask, AnnotationType, Geometries, Overview};
+use crate::point::Point;
+use crate::route::{Geometry, Leg};
+use crate::waypoints::Waypoint;
+use crate::OsrmRouteOptions;
+
+#[derive(Debug, Builder, Clone)]
+pub struct MatchRequest {
+    /// GPS trace to match, the point's `radius` is the expected GPS precision.
+    pub points : Vec<Point>,
+    /// UNIX timestamp of every point, either empty or as long as `points`.
+    #[builder(default)]
+    pub timestamps : Vec<u32>,
+    #[builder(default)]
+    pub gaps : Gaps,
+    /// Removes points that are too close to each other before matching.
+    #[builder(default)]
+    pub tidy : bool,
+    /// Indices of the points treated as waypoints, all points are waypoints when `None`.
+    #[builder(default, setter(strip_option))]
+    pub waypoints : Option<Vec<usize>>,
+    #[builder(default)]
+    pub steps: bool,
+    #[builder(default)]
+    pub annotations: Vec<AnnotationType>,
+    #[builder(default)]
+    pub overview: Overview,
+    #[builder(default)]
+    pub geometries: Geometries,
+}
+
+impl MatchRequest {
+    pub(crate) fn options(&self) -> OsrmRouteOptions {
+        OsrmRouteOptions {
+            steps: self.steps,
+            alternatives: 0,
+            annotations: annotations_mask(&self.annotations),
+            overview: self.overview.as_raw(),
+            geometries: self.geometries.as_raw(),
+            continue_straight: -1,
+        }
+    }
+}
+
+/// How the trace is split when two consecutive timestamps are far apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Gaps {
+    #[default]
+    Split,
+    Ignore,
+}
+
+impl Gaps {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            Gaps::Split => 0,
+            Gaps::Ignore => 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct MatchResponse {
+    pub code: String,
+    pub matchings: Vec<Matching>,
+    /// One entry per input point, `None` when the point could not be matched.
+    pub tracepoints: Vec<Option<Tracepoint>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Matching {
+    /// Between 0 and 1, how confident OSRM is that this is the correct match.
+    pub confidence: f64,
+    pub geometry: Option<Geometry>,
+    pub legs: Vec<Leg>,
+    pub weight_name: String,
+    pub weight: f64,
+    pub duration: f64,
+    pub distance: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Tracepoint {
+    #[serde(flatten)]
+    pub waypoint: Waypoint,
+    /// Index of the matching this point belongs to.
+    pub matchings_index: usize,
+    /// Index of the waypoint inside the matching.
+    pub waypoint_index: usize,
+    /// Number of probable alternative matchings for this point.
+    pub alternatives_count: usize,
+}
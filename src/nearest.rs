@@ -0,0 +1,20 @@
+use derive_builder::Builder;
+use serde::Deserialize;
+use crate::point::Point;
+use crate::waypoints::Waypoint;
+
+#[derive(Debug, Builder, Clone)]
+pub struct NearestRequest {
+    /// Snapping can be constrained with the point's `bearing` and `radius`.
+    pub point : Point,
+    /// Number of nearest segments to return.
+    #[builder(default = "1")]
+    pub number : u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct NearestResponse {
+    pub code: String,
+    pub waypoints: Vec<Waypoint>,
+}
@@ -0,0 +1,108 @@
+/// Number of alternative routes OSRM should search for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alternatives {
+    #[default]
+    Disabled,
+    Enabled,
+    Count(u32),
+}
+
+impl Alternatives {
+    pub(crate) fn count(&self) -> u32 {
+        match self {
+            Alternatives::Disabled => 0,
+            Alternatives::Enabled => 1,
+            Alternatives::Count(count) => *count,
+        }
+    }
+}
+
+impl From<bool> for Alternatives {
+    fn from(enabled: bool) -> Self {
+        if enabled { Alternatives::Enabled } else { Alternatives::Disabled }
+    }
+}
+
+impl From<u32> for Alternatives {
+    fn from(count: u32) -> Self {
+        if count == 0 { Alternatives::Disabled } else { Alternatives::Count(count) }
+    }
+}
+
+/// Additional per-segment metadata returned on every leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationType {
+    Duration,
+    Nodes,
+    Distance,
+    Weight,
+    Datasources,
+    Speed,
+}
+
+impl AnnotationType {
+    /// Bit used by `osrm::RouteParameters::AnnotationsType`.
+    pub(crate) fn mask(&self) -> u32 {
+        match self {
+            AnnotationType::Duration => 0x01,
+            AnnotationType::Nodes => 0x02,
+            AnnotationType::Distance => 0x04,
+            AnnotationType::Weight => 0x08,
+            AnnotationType::Datasources => 0x10,
+            AnnotationType::Speed => 0x20,
+        }
+    }
+
+    pub fn all() -> Vec<AnnotationType> {
+        vec![
+            AnnotationType::Duration,
+            AnnotationType::Nodes,
+            AnnotationType::Distance,
+            AnnotationType::Weight,
+            AnnotationType::Datasources,
+            AnnotationType::Speed,
+        ]
+    }
+}
+
+pub(crate) fn annotations_mask(annotations: &[AnnotationType]) -> u32 {
+    annotations.iter().fold(0, |mask, annotation| mask | annotation.mask())
+}
+
+/// Level of detail of the route geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overview {
+    #[default]
+    Simplified,
+    Full,
+    False,
+}
+
+impl Overview {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            Overview::Simplified => 0,
+            Overview::Full => 1,
+            Overview::False => 2,
+        }
+    }
+}
+
+/// Encoding of the returned geometries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Geometries {
+    #[default]
+    Polyline,
+    Polyline6,
+    GeoJson,
+}
+
+impl Geometries {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            Geometries::Polyline => 0,
+            Geometries::Polyline6 => 1,
+            Geometries::GeoJson => 2,
+        }
+    }
+}
@@ -1,47 +1,132 @@
 // osrm/src/lib.rs
 
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
 
+use crate::engine_config::{EngineConfig, EngineConfigBuilder};
 use crate::errors::OsrmError;
-use crate::{algorithm, Osrm};
+use crate::{algorithm, CoordinateBuffer, Osrm};
+use crate::large_table::{LargeTableRequest, LargeTableResponse, TableProgress};
+use crate::matching::{MatchRequest, MatchResponse};
+use crate::nearest::{NearestRequest, NearestResponse};
 use crate::point::Point;
-use crate::route::{RouteRequest, RouteResponse, SimpleRouteResponse};
+use crate::route::{RouteRequest, RouteRequestBuilder, RouteResponse, SimpleRouteResponse};
 use crate::tables::{TableRequest, TableResponse};
+use crate::tile::TileRequest;
 use crate::trip::{TripRequest, TripResponse};
 
 pub struct OsrmEngine {
     instance: Osrm,
+    config: EngineConfig,
 }
 
 impl OsrmEngine {
 
     pub fn new(base_path: &str, algorithm : algorithm::Algorithm) -> Result<Self, OsrmError> {
-        let osrm = Osrm::new(base_path, algorithm.as_str()).map_err( |_|  OsrmError::Initialization )?;
+        let config = EngineConfigBuilder::default().path(base_path).algorithm(algorithm).build().map_err(|_| OsrmError::InvalidPath(base_path.to_owned()))?;
+        Self::with_config(config)
+    }
+
+    pub fn with_config(config: EngineConfig) -> Result<Self, OsrmError> {
+        config.validate()?;
+        let (raw_config, _dataset_name) = config.to_raw();
+        let osrm = Osrm::new(&config.path, config.algorithm.as_str(), &raw_config)?;
         Ok(OsrmEngine {
             instance: osrm,
+            config,
         })
     }
 
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
     pub fn table(&self, table_request: TableRequest) -> Result<TableResponse, OsrmError> {
-        let len_sources = table_request.sources.len();
-        let len_destinations = table_request.destinations.len();
-        if len_sources == 0 || len_destinations == 0 {
+        if table_request.annotations.is_empty() {
             return Err(OsrmError::InvalidTableArgument);
         }
-        let sources_index: &[usize]  = &(0..(len_sources)).collect::<Vec<usize>>()[..];
-        let destination_index: &[usize]  = &(len_sources..(len_sources+len_destinations)).collect::<Vec<usize>>()[..];
-        let coordinates: &[(f64, f64)] =  &[table_request.sources, table_request.destinations].concat().iter().map( |s| (s.longitude, s.latitude) ).collect::<Vec<(f64, f64)>>()[..];
-        let result = self.instance.table(coordinates, Some(sources_index), Some(destination_index)).map_err( |e| OsrmError::FfiError(e))?;
-        serde_json::from_str::<TableResponse>(&result).map_err(|e| OsrmError::JsonParse(e))
+        if table_request.fallback_speed.is_some_and(|speed| speed <= 0.0) || table_request.scale_factor <= 0.0 {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        let options = table_request.options();
+        let coordinates = table_request.into_coordinates()?;
+        let result = self.instance.table(
+            &coordinates.points,
+            Some(&coordinates.sources),
+            Some(&coordinates.destinations),
+            &options,
+        )?;
+        Ok(TableResponse::from_result(result))
+    }
+
+    /// Computes a matrix of any size in blocks of at most `source_block_size` by
+    /// `destination_block_size`, on `threads` threads. `progress` is called after each block,
+    /// from the thread that computed it. Fails with the error of the first failing block.
+    pub fn large_table(
+        &self,
+        table_request: LargeTableRequest,
+        progress: impl Fn(TableProgress) + Sync,
+    ) -> Result<LargeTableResponse, OsrmError> {
+        table_request.validate(self.config.max_locations_distance_table)?;
+        let options = table_request.options();
+        let blocks = table_request.blocks();
+        let response = Mutex::new(LargeTableResponse::new(
+            table_request.sources.len(),
+            table_request.destinations.len(),
+            &table_request.annotations,
+        ));
+        let completed = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+
+        let results = parallel_map(&blocks, table_request.threads, |buffer, block| {
+            if failed.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let (sources, destinations) = (block.sources.1 - block.sources.0, block.destinations.1 - block.destinations.0);
+            let points = [
+                &table_request.sources[block.sources.0..block.sources.1],
+                &table_request.destinations[block.destinations.0..block.destinations.1],
+            ]
+            .concat();
+            let sources_index: Vec<usize> = (0..sources).collect();
+            let destinations_index: Vec<usize> = (sources..sources + destinations).collect();
+            let result = self.instance
+                .table_with(buffer, &points, Some(&sources_index), Some(&destinations_index), &options)
+                .inspect_err(|_| failed.store(true, Ordering::Relaxed))?;
+
+            response.lock().unwrap_or_else(PoisonError::into_inner).stitch(
+                *block,
+                result.durations.as_deref(),
+                result.distances.as_deref(),
+                &result.fallback_speed_cells,
+            );
+            let completed_blocks = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            progress(TableProgress { completed_blocks, total_blocks: blocks.len() });
+            Ok(())
+        });
+
+        results.into_iter().collect::<Result<(), OsrmError>>()?;
+        Ok(response.into_inner().unwrap_or_else(PoisonError::into_inner))
     }
 
     pub fn route(&self, route_request: RouteRequest) -> Result<RouteResponse, OsrmError> {
+        self.route_with(&mut CoordinateBuffer::default(), &route_request)
+    }
+
+    fn route_with(&self, buffer: &mut CoordinateBuffer, route_request: &RouteRequest) -> Result<RouteResponse, OsrmError> {
         let len = route_request.points.len();
         if len == 0 {
             return Err(OsrmError::InvalidTableArgument);
         }
-        let coordinates: &[(f64, f64)] = &route_request.points.iter().map( |p|  (p.longitude, p.latitude) ).collect::<Vec<(f64, f64)>>()[..];
-        let result = self.instance.route(coordinates).map_err( |e| OsrmError::FfiError(e))?;
-        serde_json::from_str::<RouteResponse>(&result).map_err(|e| OsrmError::JsonParse(e))
+        let result = self.instance.route_with(buffer, &route_request.points, &route_request.options())?;
+        serde_json::from_str::<RouteResponse>(&result).map_err(OsrmError::JsonParse)
+    }
+
+    /// Computes independent routes on all available cores. Returns one result per request,
+    /// in the input order, so that a failing request does not abort the batch.
+    pub fn route_many(&self, route_requests: impl IntoIterator<Item = RouteRequest>) -> Vec<Result<RouteResponse, OsrmError>> {
+        let route_requests: Vec<RouteRequest> = route_requests.into_iter().collect();
+        parallel_map(&route_requests, available_threads(), |buffer, route_request| self.route_with(buffer, route_request))
     }
 
     pub fn trip(&self, trip_request: TripRequest) -> Result<TripResponse, OsrmError> {
@@ -49,32 +134,137 @@ impl OsrmEngine {
         if len == 0 {
             return Err(OsrmError::InvalidTableArgument);
         }
-        let coordinates: &[(f64, f64)] =  &trip_request.points.iter().map( |p|  (p.longitude, p.latitude) ).collect::<Vec<(f64, f64)>>()[..];
-        let result = self.instance.trip(coordinates).map_err( |e| OsrmError::FfiError(e))?;
+        let result = self.instance.trip(
+            &trip_request.points,
+            trip_request.roundtrip,
+            trip_request.source.as_raw(),
+            trip_request.destination.as_raw(),
+            &trip_request.options(),
+        )?;
         serde_json::from_str::<TripResponse>(&result).map_err(|e| OsrmError::JsonParse(e))
     }
 
-    pub fn simple_route(&self, from : Point , to : Point) -> Result<SimpleRouteResponse, OsrmError> {
-        let coordinates: &[(f64, f64)] =  &[from, to].iter().map( |p |  (p.longitude, p.latitude)).collect::<Vec<(f64, f64)>>()[..];
-        let result = self.instance.route(coordinates).map_err( |e| OsrmError::FfiError(e))?;
-        let route_response = serde_json::from_str::<RouteResponse>(&result).map_err(|e| OsrmError::JsonParse(e))?;
-        if route_response.routes.len() == 0 {
-            return Err(OsrmError::ApiError("No route were returned between those 2 points".to_owned()))
+    pub fn nearest(&self, nearest_request: NearestRequest) -> Result<NearestResponse, OsrmError> {
+        if nearest_request.number == 0 {
+            return Err(OsrmError::ApiError("At least one nearest result must be requested".to_owned()));
         }
-        Ok(SimpleRouteResponse {
-            code: route_response.code,
-            distance : route_response.routes.first().unwrap().legs.first().unwrap().distance,
-            durations : route_response.routes.first().unwrap().legs.first().unwrap().duration
-        })
+        let result = self.instance.nearest(&nearest_request.point, nearest_request.number)?;
+        serde_json::from_str::<NearestResponse>(&result).map_err(OsrmError::JsonParse)
+    }
+
+    pub fn matching(&self, match_request: MatchRequest) -> Result<MatchResponse, OsrmError> {
+        let len = match_request.points.len();
+        if len < 2 {
+            return Err(OsrmError::ApiError("At least 2 points are required to match a trace".to_owned()));
+        }
+        if !match_request.timestamps.is_empty() && match_request.timestamps.len() != len {
+            return Err(OsrmError::ApiError("There must be exactly one timestamp per point".to_owned()));
+        }
+        let result = self.instance.matching(
+            &match_request.points,
+            &match_request.timestamps,
+            match_request.waypoints.as_deref(),
+            match_request.gaps.as_raw(),
+            match_request.tidy,
+            &match_request.options(),
+        )?;
+        serde_json::from_str::<MatchResponse>(&result).map_err(OsrmError::JsonParse)
+    }
+
+    /// Returns the tile as a Mapbox Vector Tile (PBF encoded).
+    pub fn tile(&self, tile_request: TileRequest) -> Result<Vec<u8>, OsrmError> {
+        self.instance.tile(tile_request.x, tile_request.y, tile_request.z)
+    }
+
+    pub fn simple_route(&self, from : Point , to : Point) -> Result<SimpleRouteResponse, OsrmError> {
+        let route_response = self.route(simple_route_request(from, to))?;
+        into_simple_route(route_response)
+    }
+
+    /// `simple_route` for many origin-destination pairs, see `route_many`.
+    pub fn simple_route_many(&self, pairs: impl IntoIterator<Item = (Point, Point)>) -> Vec<Result<SimpleRouteResponse, OsrmError>> {
+        let route_requests = pairs.into_iter().map(|(from, to)| simple_route_request(from, to));
+        self.route_many(route_requests)
+            .into_iter()
+            .map(|route_response| route_response.and_then(into_simple_route))
+            .collect()
     }
 }
 
+fn simple_route_request(from: Point, to: Point) -> RouteRequest {
+    RouteRequestBuilder::default().points(vec![from, to]).build().expect("points are always set")
+}
+
+fn into_simple_route(route_response: RouteResponse) -> Result<SimpleRouteResponse, OsrmError> {
+    let Some(leg) = route_response.routes.first().and_then(|route| route.legs.first()) else {
+        return Err(OsrmError::ApiError("No route were returned between those 2 points".to_owned()));
+    };
+    Ok(SimpleRouteResponse {
+        code: route_response.code.clone(),
+        distance: leg.distance,
+        durations: leg.duration,
+    })
+}
+
+fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Applies `task` to every item on up to `threads` scoped threads, each with its own coordinate
+/// buffer, and returns the results in the order of `items`.
+fn parallel_map<I: Sync, T: Send>(
+    items: &[I],
+    threads: usize,
+    task: impl Fn(&mut CoordinateBuffer, &I) -> T + Sync,
+) -> Vec<T> {
+    let threads = threads.clamp(1, items.len().max(1));
+    if threads == 1 {
+        let mut buffer = CoordinateBuffer::default();
+        return items.iter().map(|item| task(&mut buffer, item)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<T>> = std::iter::repeat_with(|| None).take(items.len()).collect();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut buffer = CoordinateBuffer::default();
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else { break };
+                        done.push((index, task(&mut buffer, item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for worker in workers {
+            let done = worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, result) in done {
+                results[index] = Some(result);
+            }
+        }
+    });
+    results.into_iter().map(|result| result.expect("every item is processed")).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*; // Import OsrmEngine, TableRequest, etc.
     use crate::algorithm::Algorithm;
-    use crate::route::RouteRequestBuilder;
-    use crate::tables::{Point};
+    use crate::datastore::{self, LoadRequestBuilder};
+    use crate::large_table::LargeTableRequestBuilder;
+    use crate::matching::MatchRequestBuilder;
+    use crate::nearest::NearestRequestBuilder;
+    use crate::options::{AnnotationType, Geometries, Overview};
+    use crate::pipeline::{self, ExtractConfigBuilder, PipelineConfig};
+    use crate::point::{Approach, Bearing};
+    use crate::tile::TileRequestBuilder;
+    use crate::trip::{TripDestination, TripRequestBuilder, TripSource};
+    use crate::route::{Geometry, ManeuverType, RouteRequestBuilder};
+    use crate::tables::{FallbackCoordinate, Point, TableAnnotation, TableRequestBuilder};
     #[test]
     fn it_calculates_a_table_successfully() {
         dotenvy::dotenv().expect(".env file could not be read");
@@ -82,15 +272,16 @@ mod tests {
             .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
         let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
 
-        let request = TableRequest {
-            sources: vec![
-                Point { longitude: 2.3522, latitude: 48.8566 } // Paris
-            ],
-            destinations: vec![
-                Point { longitude: 5.3698, latitude: 43.2965 }, // Marseille
-                Point { longitude: 4.8357, latitude: 45.7640 }  // Lyon
-            ]
-        };
+        let request = TableRequestBuilder::default()
+            .sources(vec![
+                Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() } // Paris
+            ])
+            .destinations(vec![
+                Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+                Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }  // Lyon
+            ])
+            .build()
+            .expect("Failed to build TableRequest");
         let response = engine.table(request).expect("Table request failed");
 
         println!("{:?}", response.durations);
@@ -102,6 +293,208 @@ mod tests {
         assert!(response.durations[0][1].is_some(), "Paris-Lyon duration should exist");
     }
 
+    #[test]
+    fn it_calculates_a_distance_table_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let request = TableRequestBuilder::default()
+            .sources(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }]) // Paris
+            .destinations(vec![Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }]) // Marseille
+            .annotations(vec![TableAnnotation::Duration, TableAnnotation::Distance])
+            .build()
+            .expect("Failed to build TableRequest");
+        let response = engine.table(request).expect("Table request failed");
+
+        assert_eq!(response.code, "Ok");
+        assert!(response.durations[0][0].is_some(), "Paris-Marseille duration should exist");
+        let distances = response.distances.expect("Distances should be returned");
+        let distance = distances[0][0].expect("Paris-Marseille distance should exist") / 1000.0; // kilometer
+        assert!(  700.0 < distance  && distance < 800.0 ); // between 700 and 800 km (google map used)
+    }
+
+    #[test]
+    fn it_fills_a_rectangular_table_in_row_major_order() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let marseille = Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        let request = TableRequestBuilder::default()
+            .sources(vec![paris.clone(), lyon.clone()])
+            .destinations(vec![paris, marseille, lyon])
+            .annotations(vec![TableAnnotation::Distance])
+            .build()
+            .expect("Failed to build TableRequest");
+        let response = engine.table(request).expect("Table request failed");
+
+        assert!(response.durations.is_empty(), "Durations were not requested");
+        let distances = response.distances.expect("Distances should be returned");
+        assert_eq!(distances.len(), 2, "Should have 1 row per source");
+        assert!(distances.iter().all(|row| row.len() == 3), "Should have 1 column per destination");
+        assert_eq!(distances[0][0], Some(0.0), "Paris-Paris should be empty");
+        assert_eq!(distances[1][2], Some(0.0), "Lyon-Lyon should be empty");
+        assert!(distances[0][1].unwrap() > distances[1][1].unwrap(), "Marseille is closer to Lyon than to Paris");
+    }
+
+    #[test]
+    fn it_calculates_a_table_over_a_single_point_list_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let marseille = Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        let points = vec![paris.clone(), marseille.clone(), lyon.clone()];
+
+        let all_pairs = TableRequestBuilder::default().points(points.clone()).build().expect("Failed to build TableRequest");
+        let response = engine.table(all_pairs).expect("Table request failed");
+        assert_eq!(response.durations.len(), 3, "Should have 1 row per point");
+        assert!(response.durations.iter().all(|row| row.len() == 3), "Should have 1 column per point");
+        assert_eq!(response.durations[1][1], Some(0.0), "Marseille-Marseille should be empty");
+
+        let subset = TableRequestBuilder::default()
+            .points(points.clone())
+            .source_indices(vec![0, 2])
+            .destination_indices(vec![1])
+            .build()
+            .expect("Failed to build TableRequest");
+        let response = engine.table(subset).expect("Table request failed");
+        let expected = engine
+            .table(TableRequestBuilder::default().sources(vec![paris, lyon]).destinations(vec![marseille]).build().expect("Failed to build TableRequest"))
+            .expect("Table request failed");
+        assert_eq!(response.durations, expected.durations, "Indices should select the same rows and columns");
+
+        let out_of_range = TableRequestBuilder::default().points(points).destination_indices(vec![3]).build().expect("Failed to build TableRequest");
+        assert!(matches!(engine.table(out_of_range), Err(OsrmError::InvalidTableArgument)));
+    }
+
+    /// Builds a dataset of two roads that are not connected to each other.
+    fn build_islands_dataset() -> String {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let profile = std::env::var("OSRM_TEST_PROFILE_PATH")
+            .expect("Environment variable OSRM_TEST_PROFILE_PATH must be defined with a Lua profile");
+        let output = std::env::temp_dir().join(format!("osrm-binding-islands-{}", std::process::id()));
+        std::fs::create_dir_all(&output).expect("Failed to create the output directory");
+        let input = output.join("islands.osm");
+        std::fs::write(&input, r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6" generator="osrm-binding">
+  <node id="1" version="1" lat="43.7300" lon="7.4200"/>
+  <node id="2" version="1" lat="43.7320" lon="7.4200"/>
+  <node id="3" version="1" lat="43.7400" lon="7.4300"/>
+  <node id="4" version="1" lat="43.7420" lon="7.4300"/>
+  <way id="1" version="1"><nd ref="1"/><nd ref="2"/><tag k="highway" v="residential"/></way>
+  <way id="2" version="1"><nd ref="3"/><nd ref="4"/><tag k="highway" v="residential"/></way>
+</osm>
+"#).expect("Failed to write the OSM file");
+        let path = output.join("islands.osrm").to_string_lossy().into_owned();
+
+        let config = ExtractConfigBuilder::default()
+            .input_path(input.to_string_lossy().into_owned())
+            .profile_path(profile)
+            .output_path(path.clone())
+            .build()
+            .expect("Failed to build ExtractConfig");
+        pipeline::extract(config).expect("extract failed");
+        let config = PipelineConfig { path: path.clone(), threads: None, segment_speed_files: Vec::new(), turn_penalty_files: Vec::new() };
+        pipeline::partition(config.clone()).expect("partition failed");
+        pipeline::customize(config).expect("customize failed");
+        path
+    }
+
+    #[test]
+    fn it_estimates_unreachable_cells_with_the_fallback_speed() {
+        let path = build_islands_dataset();
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let table = |fallback_speed: f64| {
+            let request = TableRequestBuilder::default()
+                .sources(vec![Point { longitude: 7.4200, latitude: 43.7305, ..Default::default() }])
+                .destinations(vec![
+                    Point { longitude: 7.4200, latitude: 43.7315, ..Default::default() }, // same road
+                    Point { longitude: 7.4300, latitude: 43.7410, ..Default::default() }, // other road, unreachable
+                ])
+                .fallback_speed(fallback_speed)
+                .fallback_coordinate(FallbackCoordinate::Snapped)
+                .build()
+                .expect("Failed to build TableRequest");
+            engine.table(request).expect("Table request failed")
+        };
+        let slow = table(25.0);
+        let fast = table(50.0);
+
+        assert_eq!(slow.code, "Ok");
+        assert_eq!(slow.fallback_speed_cells, Some(vec![[0, 1]]), "Only the unreachable cell should be estimated");
+        assert_eq!(fast.fallback_speed_cells, Some(vec![[0, 1]]), "Only the unreachable cell should be estimated");
+        let slow_estimate = slow.durations[0][1].expect("The unreachable cell should be estimated");
+        let fast_estimate = fast.durations[0][1].expect("The unreachable cell should be estimated");
+        assert!(fast_estimate > 0.0);
+        assert!((slow_estimate / fast_estimate - 2.0).abs() < 0.01, "Halving the fallback speed should double the estimate");
+        assert_eq!(slow.durations[0][0], fast.durations[0][0], "Routed cells should not depend on the fallback speed");
+    }
+
+    #[test]
+    fn it_enforces_the_engine_config_limits() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+
+        let invalid = EngineConfigBuilder::default().path(&*path).max_locations_distance_table(0).build().expect("Failed to build EngineConfig");
+        assert!(matches!(OsrmEngine::with_config(invalid), Err(OsrmError::InvalidConfig(_))));
+
+        let config = EngineConfigBuilder::default()
+            .path(&*path)
+            .algorithm(Algorithm::MLD)
+            .max_locations_distance_table(2)
+            .build()
+            .expect("Failed to build EngineConfig");
+        let engine = OsrmEngine::with_config(config).expect("Failed to initialize OSRM engine");
+
+        let request = TableRequestBuilder::default()
+            .sources(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }]) // Paris
+            .destinations(vec![
+                Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+                Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }, // Lyon
+            ])
+            .build()
+            .expect("Failed to build TableRequest");
+        assert!(matches!(engine.table(request), Err(OsrmError::TooBig(_))), "3 locations should exceed the table limit");
+    }
+
+    #[test]
+    fn it_serves_a_dataset_from_shared_memory() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+
+        let load_request = LoadRequestBuilder::default().path(&*path).dataset_name("osrm-binding-test").build().expect("Failed to build LoadRequest");
+        datastore::load(load_request).expect("Failed to load the dataset into shared memory");
+        let regions = datastore::list().expect("Failed to list shared memory regions");
+        assert!(regions.iter().any(|region| region.name.starts_with("osrm-binding-test/")), "The dataset should be registered");
+
+        let config = EngineConfigBuilder::default()
+            .algorithm(Algorithm::MLD)
+            .use_shared_memory(true)
+            .dataset_name("osrm-binding-test")
+            .build()
+            .expect("Failed to build EngineConfig");
+        let engine = OsrmEngine::with_config(config).expect("Failed to initialize OSRM engine");
+        let response = engine.simple_route(Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point {  longitude: 5.3698, latitude: 43.2965, ..Default::default() }).expect("route request failed");
+        assert_eq!(response.code, "Ok");
+        drop(engine);
+
+        datastore::drop_dataset("osrm-binding-test").expect("Failed to drop the dataset");
+        let regions = datastore::list().expect("Failed to list shared memory regions");
+        assert!(!regions.iter().any(|region| region.name.starts_with("osrm-binding-test/")), "The dataset should be removed");
+    }
+
     #[test]
     fn it_calculates_a_route_successfully() {
         dotenvy::dotenv().expect(".env file could not be read");
@@ -109,7 +502,7 @@ mod tests {
             .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
         let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
 
-        let request = RouteRequestBuilder::default().points(vec![Point { longitude: 2.3522, latitude: 48.8566 }, Point {  longitude: 5.3698, latitude: 43.2965 }]).build().expect("Failed to build RouteRequest");
+        let request = RouteRequestBuilder::default().points(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }]).build().expect("Failed to build RouteRequest");
         let response = engine.route(request).expect("route request failed");
 
         let duration = response.routes.first().unwrap().legs.first().unwrap().duration;
@@ -120,16 +513,330 @@ mod tests {
         assert!(  27000.0 < duration  && duration < 30600.0 ); // between 7h30 and 8h30 (google map used)
     }
 
+    #[test]
+    fn it_calculates_a_route_with_options_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let request = RouteRequestBuilder::default()
+            .points(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }])
+            .alternatives(2)
+            .steps(true)
+            .overview(Overview::Full)
+            .geometries(Geometries::GeoJson)
+            .build()
+            .expect("Failed to build RouteRequest");
+        let response = engine.route(request).expect("route request failed");
+
+        assert_eq!(response.code, "Ok");
+        assert!(!response.routes.is_empty() && response.routes.len() <= 3, "Should have the main route and at most 2 alternatives");
+        let route = response.routes.first().unwrap();
+        assert!(matches!(route.geometry, Some(Geometry::GeoJson(_))), "Geometry should be GeoJSON");
+        let steps = &route.legs.first().unwrap().steps;
+        assert!(!steps.is_empty(), "Steps should be returned");
+        assert_eq!(steps.first().unwrap().maneuver.maneuver_type, ManeuverType::Depart);
+        assert_eq!(steps.last().unwrap().maneuver.maneuver_type, ManeuverType::Arrive);
+        assert!(steps.iter().all(|step| !step.intersections.is_empty()), "Every step should have at least one intersection");
+    }
+
+    #[test]
+    fn it_calculates_a_route_with_annotations_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let request = RouteRequestBuilder::default()
+            .points(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 2.2945, latitude: 48.8584, ..Default::default() }])
+            .annotations(vec![AnnotationType::Nodes, AnnotationType::Speed, AnnotationType::Datasources])
+            .build()
+            .expect("Failed to build RouteRequest");
+        let response = engine.route(request).expect("route request failed");
+
+        let annotation = response.routes[0].legs[0].annotation.as_ref().expect("Annotation should be returned");
+        let nodes = annotation.nodes.as_ref().expect("Nodes should be returned");
+        let speed = annotation.speed.as_ref().expect("Speed should be returned");
+        let datasources = annotation.datasources.as_ref().expect("Datasources should be returned");
+        assert_eq!(nodes.len(), speed.len() + 1, "There should be one more node than segments");
+        assert_eq!(speed.len(), datasources.len());
+        assert!(annotation.duration.is_none(), "Duration was not requested");
+        assert!(!annotation.metadata.as_ref().expect("Metadata should be returned").datasource_names.is_empty());
+    }
+
+    #[test]
+    fn it_calculates_a_route_with_snapping_constraints_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let from = Point { longitude: 2.3522, latitude: 48.8566, radius: Some(500.0), approach: Some(Approach::Curb), ..Default::default() };
+        let to = Point { longitude: 5.3698, latitude: 43.2965, bearing: Some(Bearing { value: 90, range: 180 }), ..Default::default() };
+        let request = RouteRequestBuilder::default().points(vec![from.clone(), to]).build().expect("Failed to build RouteRequest");
+        let response = engine.route(request).expect("route request failed");
+        assert_eq!(response.code, "Ok");
+
+        // Re-using the hint of a previous response should snap to the same location
+        let hinted = Point { hint: Some(response.waypoints[0].hint.clone()), ..from };
+        let to = Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() };
+        let request = RouteRequestBuilder::default().points(vec![hinted, to]).build().expect("Failed to build RouteRequest");
+        let hinted_response = engine.route(request).expect("route request failed");
+        assert_eq!(hinted_response.waypoints[0].location, response.waypoints[0].location);
+    }
+
+    #[test]
+    #[cfg(not(osrm_v5))]
+    fn it_calculates_a_route_with_an_opposite_approach_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let from = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let to = Point { longitude: 4.8357, latitude: 45.7640, approach: Some(Approach::Opposite), ..Default::default() };
+        let request = RouteRequestBuilder::default().points(vec![from, to]).build().expect("Failed to build RouteRequest");
+        let response = engine.route(request).expect("route request failed");
+        assert_eq!(response.code, "Ok");
+    }
+
+    #[test]
+    fn it_finds_the_nearest_segments_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let request = NearestRequestBuilder::default()
+            .point(Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() })
+            .number(3)
+            .build()
+            .expect("Failed to build NearestRequest");
+        let response = engine.nearest(request).expect("nearest request failed");
+
+        assert_eq!(response.code, "Ok");
+        assert_eq!(response.waypoints.len(), 3, "Should have 3 nearest waypoints");
+        assert!(response.waypoints.iter().all(|waypoint| waypoint.nodes.is_some()), "Nearest waypoints should carry their nodes");
+        assert!(response.waypoints.windows(2).all(|w| w[0].distance <= w[1].distance), "Waypoints should be sorted by distance");
+    }
+
+    #[test]
+    fn it_matches_a_gps_trace_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        // A few pings along the Champs-Élysées, 20 seconds apart
+        let points: Vec<Point> = [(2.2950, 48.8738), (2.2980, 48.8725), (2.3010, 48.8712), (2.3040, 48.8698)]
+            .iter()
+            .map(|&(longitude, latitude)| Point { longitude, latitude, radius: Some(20.0), ..Default::default() })
+            .collect();
+        let request = MatchRequestBuilder::default()
+            .points(points)
+            .timestamps(vec![1_700_000_000, 1_700_000_020, 1_700_000_040, 1_700_000_060])
+            .tidy(true)
+            .build()
+            .expect("Failed to build MatchRequest");
+        let response = engine.matching(request).expect("match request failed");
+
+        assert_eq!(response.code, "Ok");
+        assert!(!response.matchings.is_empty(), "Should have at least one matching");
+        assert_eq!(response.tracepoints.len(), 4, "Should have one tracepoint per point");
+        let matching = response.matchings.first().unwrap();
+        assert!((0.0..=1.0).contains(&matching.confidence));
+        assert!(response.tracepoints.iter().flatten().all(|tracepoint| tracepoint.matchings_index < response.matchings.len()));
+    }
+
+    #[test]
+    fn it_renders_a_tile_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        // Tile covering the center of Paris
+        let request = TileRequestBuilder::default().x(8299).y(5637).z(14).build().expect("Failed to build TileRequest");
+        let tile = engine.tile(request).expect("tile request failed");
+        assert!(!tile.is_empty(), "Tile should not be empty");
+
+        let request = TileRequestBuilder::default().x(0).y(0).z(1).build().expect("Failed to build TileRequest");
+        assert!(matches!(engine.tile(request), Err(OsrmError::InvalidValue(_))), "Zoom levels below 12 are not supported");
+    }
+
+    #[test]
+    fn it_calculates_an_open_trip_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let request = TripRequestBuilder::default()
+            .points(vec![
+                Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, // Paris (depot)
+                Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+                Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }, // Lyon
+                Point { longitude: 3.0573, latitude: 50.6292, ..Default::default() }, // Lille
+            ])
+            .roundtrip(false)
+            .source(TripSource::First)
+            .destination(TripDestination::Last)
+            .build()
+            .expect("Failed to build TripRequest");
+        let response = engine.trip(request).expect("trip request failed");
+
+        assert_eq!(response.code, "Ok");
+        assert_eq!(response.trips.len(), 1, "Should have a single trip");
+        assert_eq!(response.trips[0].legs.len(), 3, "An open trip over 4 points has 3 legs");
+        assert_eq!(response.waypoints.len(), 4, "Should have one waypoint per point");
+        assert_eq!(response.waypoints[0].waypoint_index, 0, "The trip should start at the depot");
+        assert_eq!(response.waypoints[3].waypoint_index, 3, "The trip should end at the last point");
+    }
+
+    #[test]
+    fn it_reports_typed_osrm_errors() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        // In the middle of the Bay of Biscay, no road within 10 meters
+        let sea = Point { longitude: -5.0, latitude: 45.5, radius: Some(10.0), ..Default::default() };
+        let request = RouteRequestBuilder::default().points(vec![Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, sea]).build().expect("Failed to build RouteRequest");
+        assert!(matches!(engine.route(request), Err(OsrmError::NoSegment(_))));
+    }
+
+    #[test]
+    fn it_reports_why_a_dataset_failed_to_load() {
+        match OsrmEngine::new("/nonexistent/dataset.osrm", Algorithm::MLD) {
+            Err(OsrmError::Initialization(message)) => assert!(!message.is_empty(), "The failure reason should be reported"),
+            other => panic!("Expected an initialization error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn it_reports_exceptions_as_errors() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        // Decoding a malformed hint throws inside OSRM, it must come back as an error instead of aborting
+        let from = Point { longitude: 2.3522, latitude: 48.8566, hint: Some("not a hint".to_owned()), ..Default::default() };
+        let request = RouteRequestBuilder::default().points(vec![from, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }]).build().expect("Failed to build RouteRequest");
+        assert!(engine.route(request).is_err());
+    }
+
     #[test]
     fn it_calculates_a_simple_route_successfully() {
         dotenvy::dotenv().expect(".env file could not be read");
         let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
             .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
         let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
-        let response = engine.simple_route(Point { longitude: 2.3522, latitude: 48.8566 }, Point {  longitude: 5.3698, latitude: 43.2965 }).expect("route request failed");
+        let response = engine.simple_route(Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }).expect("route request failed");
         assert_eq!(response.code, "Ok");
         println!("{:?}", response);
         assert!(  700.0 < (response.distance / 1000.0)  && (response.distance  / 1000.0) < 800.0); // between 700 and 800 km (google map used)
         assert!(  27000.0 < response.durations  && response.durations < 30600.0 ); // between 7h30 and 8h30 (google map used)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn it_calculates_many_routes_in_input_order_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        let marseille = Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() };
+        let sea = Point { longitude: -5.0, latitude: 45.5, radius: Some(10.0), ..Default::default() };
+        let pairs = vec![
+            (paris.clone(), lyon.clone()),
+            (paris.clone(), sea),
+            (lyon.clone(), marseille.clone()),
+            (paris.clone(), marseille.clone()),
+        ];
+
+        let responses = engine.simple_route_many(pairs.clone());
+        assert_eq!(responses.len(), pairs.len(), "Should return one result per pair");
+        assert!(matches!(responses[1], Err(OsrmError::NoSegment(_))), "The unreachable pair should fail on its own");
+        for (index, (from, to)) in pairs.into_iter().enumerate().filter(|(index, _)| *index != 1) {
+            let expected = engine.simple_route(from, to).expect("route request failed");
+            let response = responses[index].as_ref().expect("route request failed");
+            assert_eq!(response.distance, expected.distance, "Pair {} should keep its position", index);
+        }
+
+        let requests = vec![
+            RouteRequestBuilder::default().points(vec![paris, lyon, marseille]).build().expect("Failed to build RouteRequest"),
+            RouteRequestBuilder::default().points(vec![]).build().expect("Failed to build RouteRequest"),
+        ];
+        let responses = engine.route_many(requests);
+        assert_eq!(responses[0].as_ref().expect("route request failed").routes[0].legs.len(), 2);
+        assert!(matches!(responses[1], Err(OsrmError::InvalidTableArgument)));
+    }
+
+    #[test]
+    fn it_calculates_a_large_table_in_blocks_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = OsrmEngine::new(&*path, Algorithm::MLD).expect("Failed to initialize OSRM engine");
+
+        let sources = vec![
+            Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() }, // Paris
+            Point { longitude: 5.3698, latitude: 43.2965, ..Default::default() }, // Marseille
+            Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }, // Lyon
+        ];
+        let destinations = vec![
+            Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() }, // Lyon
+            Point { longitude: 1.4442, latitude: 43.6047, ..Default::default() }, // Toulouse
+        ];
+        let expected = engine
+            .table(
+                TableRequestBuilder::default()
+                    .sources(sources.clone())
+                    .destinations(destinations.clone())
+                    .annotations(vec![TableAnnotation::Duration, TableAnnotation::Distance])
+                    .build()
+                    .expect("Failed to build TableRequest"),
+            )
+            .expect("Table request failed");
+
+        let request = LargeTableRequestBuilder::default()
+            .sources(sources)
+            .destinations(destinations)
+            .annotations(vec![TableAnnotation::Duration, TableAnnotation::Distance])
+            .source_block_size(2usize)
+            .destination_block_size(1usize)
+            .threads(2usize)
+            .build()
+            .expect("Failed to build LargeTableRequest");
+        let progress = std::sync::Mutex::new(Vec::new());
+        let response = engine
+            .large_table(request, |step| progress.lock().unwrap().push(step))
+            .expect("Large table request failed");
+
+        assert_eq!((response.rows, response.columns), (3, 2));
+        let distances = expected.distances.expect("Distances should be returned");
+        for (row, (durations, distances)) in expected.durations.iter().zip(&distances).enumerate() {
+            for (column, (duration, distance)) in durations.iter().zip(distances).enumerate() {
+                assert_eq!(response.duration(row, column), duration.map(|duration| duration as f32));
+                assert_eq!(response.distance(row, column), distance.map(|distance| distance as f32));
+            }
+        }
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 4, "2 source blocks by 2 destination blocks");
+        assert!(progress.iter().all(|step| step.total_blocks == 4));
+        assert_eq!(progress.iter().map(|step| step.completed_blocks).max(), Some(4));
+    }
+
+    #[test]
+    fn it_maps_items_in_order_on_several_threads() {
+        let items: Vec<usize> = (0..1000).collect();
+        let doubled = parallel_map(&items, 4, |_, item| item * 2);
+        assert_eq!(doubled, items.iter().map(|item| item * 2).collect::<Vec<_>>());
+        assert!(parallel_map(&[] as &[usize], 4, |_, item| *item).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! Rust equivalent of the OSRM command line tools building a `.osrm` dataset from an
+//! OpenStreetMap extract: `extract`, then `partition` and `customize` for MLD or `contract` for CH.
+use std::fmt;
+use std::path::Path;
+use derive_builder::Builder;
+use crate::errors::OsrmError;
+
+/// Step of the preprocessing pipeline, reported by `OsrmError::Pipeline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStage {
+    Extract,
+    Partition,
+    Customize,
+    Contract,
+}
+
+impl fmt::Display for PipelineStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PipelineStage::Extract => "extract",
+            PipelineStage::Partition => "partition",
+            PipelineStage::Customize => "customize",
+            PipelineStage::Contract => "contract",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Builder, Clone)]
+pub struct ExtractConfig {
+    /// OpenStreetMap extract, usually a `.osm.pbf` file.
+    #[builder(setter(into))]
+    pub input_path: String,
+    /// Lua profile, e.g. `profiles/car.lua` of the OSRM sources.
+    #[builder(setter(into))]
+    pub profile_path: String,
+    /// Path of the `.osrm` dataset to write, next to the input when `None`.
+    #[builder(default, setter(into, strip_option))]
+    pub output_path: Option<String>,
+    /// Every available core when `None`.
+    #[builder(default, setter(strip_option))]
+    pub threads: Option<usize>,
+}
+
+/// Configuration of the stages working on an extracted dataset.
+#[derive(Debug, Builder, Clone)]
+pub struct PipelineConfig {
+    /// Path of the `.osrm` dataset written by `extract`.
+    #[builder(setter(into))]
+    pub path: String,
+    /// Every available core when `None`.
+    #[builder(default, setter(strip_option))]
+    pub threads: Option<usize>,
+    /// CSV files of `from_osm_node,to_osm_node,speed_kmh` overriding segment speeds.
+    /// Only used by `customize` and `contract`.
+    #[builder(default)]
+    pub segment_speed_files: Vec<String>,
+    /// CSV files of `from_osm_node,via_osm_node,to_osm_node,penalty_seconds` overriding turn
+    /// penalties. Only used by `customize` and `contract`.
+    #[builder(default)]
+    pub turn_penalty_files: Vec<String>,
+}
+
+fn raw_threads(threads: Option<usize>) -> Result<i32, OsrmError> {
+    match threads {
+        None => Ok(0),
+        Some(count) if count > 0 && count <= i32::MAX as usize => Ok(count as i32),
+        Some(_) => Err(OsrmError::InvalidConfig("threads must be between 1 and i32::MAX".to_owned())),
+    }
+}
+
+impl PipelineConfig {
+    fn validate(&self) -> Result<i32, OsrmError> {
+        if self.path.is_empty() {
+            return Err(OsrmError::InvalidPath(self.path.clone()));
+        }
+        raw_threads(self.threads)
+    }
+}
+
+/// Parses the OpenStreetMap extract with the profile, like `osrm-extract`.
+pub fn extract(config: ExtractConfig) -> Result<(), OsrmError> {
+    for path in [&config.input_path, &config.profile_path] {
+        if !Path::new(path).is_file() {
+            return Err(OsrmError::InvalidPath(path.clone()));
+        }
+    }
+    let threads = raw_threads(config.threads)?;
+    crate::pipeline_extract(&config.input_path, &config.profile_path, config.output_path.as_deref(), threads)
+}
+
+/// Splits the graph into cells for the MLD algorithm, like `osrm-partition`.
+pub fn partition(config: PipelineConfig) -> Result<(), OsrmError> {
+    let threads = config.validate()?;
+    crate::pipeline_partition(&config, threads)
+}
+
+/// Computes the MLD cell metrics, like `osrm-customize`. Needs a partitioned dataset.
+pub fn customize(config: PipelineConfig) -> Result<(), OsrmError> {
+    let threads = config.validate()?;
+    crate::pipeline_customize(&config, threads)
+}
+
+/// Builds the contraction hierarchy for the CH algorithm, like `osrm-contract`.
+pub fn contract(config: PipelineConfig) -> Result<(), OsrmError> {
+    let threads = config.validate()?;
+    crate::pipeline_contract(&config, threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::osrm_engine::OsrmEngine;
+
+    #[test]
+    fn it_rejects_a_missing_extract() {
+        let config = ExtractConfigBuilder::default()
+            .input_path("/does/not/exist.osm.pbf")
+            .profile_path("/does/not/exist.lua")
+            .build()
+            .expect("Failed to build ExtractConfig");
+
+        assert!(matches!(extract(config), Err(OsrmError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn it_builds_a_dataset_for_both_algorithms_successfully() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let pbf = std::env::var("OSRM_TEST_PBF_PATH")
+            .expect("Environment variable OSRM_TEST_PBF_PATH must be defined with a small .osm.pbf extract");
+        let profile = std::env::var("OSRM_TEST_PROFILE_PATH")
+            .expect("Environment variable OSRM_TEST_PROFILE_PATH must be defined with a Lua profile");
+        let output = std::env::temp_dir().join(format!("osrm-binding-pipeline-{}", std::process::id()));
+        std::fs::create_dir_all(&output).expect("Failed to create the output directory");
+        let path = output.join("extract.osrm").to_string_lossy().into_owned();
+
+        let config = ExtractConfigBuilder::default()
+            .input_path(pbf)
+            .profile_path(profile)
+            .output_path(path.clone())
+            .threads(2)
+            .build()
+            .expect("Failed to build ExtractConfig");
+        extract(config).expect("extract failed");
+
+        let config = PipelineConfigBuilder::default().path(path.clone()).build().expect("Failed to build PipelineConfig");
+        partition(config.clone()).expect("partition failed");
+        customize(config.clone()).expect("customize failed");
+        contract(config).expect("contract failed");
+
+        OsrmEngine::new(&path, Algorithm::MLD).expect("Failed to load the MLD dataset");
+        OsrmEngine::new(&path, Algorithm::CH).expect("Failed to load the CH dataset");
+
+        std::fs::remove_dir_all(&output).expect("Failed to remove the output directory");
+    }
+}
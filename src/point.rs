@@ -1,7 +1,46 @@
 use derive_builder::Builder;
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, Default, Builder)]
 pub struct Point {
     pub latitude : f64,
     pub longitude : f64,
-}
\ No newline at end of file
+    /// Only snap to road segments heading in this direction.
+    #[builder(default, setter(strip_option))]
+    pub bearing : Option<Bearing>,
+    /// Maximum snapping distance in meters, unlimited when `None`.
+    #[builder(default, setter(strip_option))]
+    pub radius : Option<f64>,
+    /// Base64 hint taken from a `Waypoint` of a previous response.
+    #[builder(default, setter(into, strip_option))]
+    pub hint : Option<String>,
+    #[builder(default, setter(strip_option))]
+    pub approach : Option<Approach>,
+}
+
+/// Bearing in degrees clockwise from true north (0-360), with an allowed deviation (0-180).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bearing {
+    pub value : u16,
+    pub range : u16,
+}
+
+/// Side of the road from which a waypoint must be approached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approach {
+    Unrestricted,
+    Curb,
+    /// Arrive on the opposite side of the road, added in OSRM 6.
+    #[cfg(not(osrm_v5))]
+    Opposite,
+}
+
+impl Approach {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            Approach::Unrestricted => 0,
+            Approach::Curb => 1,
+            #[cfg(not(osrm_v5))]
+            Approach::Opposite => 2,
+        }
+    }
+}
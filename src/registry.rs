@@ -0,0 +1,190 @@
+//! Several datasets loaded side by side under profile names, e.g. `car`, `bike` or `foot`.
+use std::collections::HashMap;
+use derive_builder::Builder;
+use crate::algorithm::Algorithm;
+use crate::engine_config::EngineConfig;
+use crate::errors::OsrmError;
+use crate::large_table::{LargeTableRequest, LargeTableResponse, TableProgress};
+use crate::matching::{MatchRequest, MatchResponse};
+use crate::nearest::{NearestRequest, NearestResponse};
+use crate::point::Point;
+use crate::reloadable::ReloadableOsrmEngine;
+use crate::route::{RouteRequest, RouteResponse, SimpleRouteResponse};
+use crate::tables::{TableRequest, TableResponse};
+use crate::tile::TileRequest;
+use crate::trip::{TripRequest, TripResponse};
+
+#[derive(Debug, Builder, Clone)]
+pub struct ProfileConfig {
+    #[builder(setter(into))]
+    pub name: String,
+    pub engine: EngineConfig,
+    /// Free-form metadata returned by `OsrmRegistry::metadata`, e.g. a vehicle type or capacity.
+    #[builder(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// Description of a loaded profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileMetadata {
+    pub name: String,
+    /// Path of the `.osrm` dataset, empty when served from shared memory.
+    pub path: String,
+    pub algorithm: Algorithm,
+    pub dataset_name: Option<String>,
+    pub labels: HashMap<String, String>,
+}
+
+struct Profile {
+    engine: ReloadableOsrmEngine,
+    labels: HashMap<String, String>,
+}
+
+/// Engines by profile name. Each profile can be reloaded on its own through `engine`.
+#[derive(Default)]
+pub struct OsrmRegistry {
+    profiles: HashMap<String, Profile>,
+}
+
+impl OsrmRegistry {
+    /// Loads every profile, failing on the first one that cannot be loaded.
+    pub fn new(profiles: Vec<ProfileConfig>) -> Result<Self, OsrmError> {
+        let mut registry = OsrmRegistry::default();
+        for profile in profiles {
+            registry.register(profile)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, profile: ProfileConfig) -> Result<(), OsrmError> {
+        if self.profiles.contains_key(&profile.name) {
+            return Err(OsrmError::InvalidConfig(format!("profile {} is already registered", profile.name)));
+        }
+        let engine = ReloadableOsrmEngine::new(profile.engine)?;
+        self.profiles.insert(profile.name, Profile { engine, labels: profile.labels });
+        Ok(())
+    }
+
+    /// Returns whether the profile was registered. Its dataset is released once its
+    /// running requests complete.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.profiles.remove(name).is_some()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    pub fn engine(&self, name: &str) -> Result<&ReloadableOsrmEngine, OsrmError> {
+        self.profile(name).map(|profile| &profile.engine)
+    }
+
+    pub fn metadata(&self, name: &str) -> Result<ProfileMetadata, OsrmError> {
+        let profile = self.profile(name)?;
+        let engine = profile.engine.engine();
+        let config = engine.config();
+        Ok(ProfileMetadata {
+            name: name.to_owned(),
+            path: config.path.clone(),
+            algorithm: config.algorithm,
+            dataset_name: config.dataset_name.clone(),
+            labels: profile.labels.clone(),
+        })
+    }
+
+    fn profile(&self, name: &str) -> Result<&Profile, OsrmError> {
+        self.profiles.get(name).ok_or_else(|| OsrmError::UnknownProfile(name.to_owned()))
+    }
+
+    pub fn table(&self, profile: &str, table_request: TableRequest) -> Result<TableResponse, OsrmError> {
+        self.engine(profile)?.table(table_request)
+    }
+
+    pub fn large_table(
+        &self,
+        profile: &str,
+        table_request: LargeTableRequest,
+        progress: impl Fn(TableProgress) + Sync,
+    ) -> Result<LargeTableResponse, OsrmError> {
+        self.engine(profile)?.large_table(table_request, progress)
+    }
+
+    pub fn route(&self, profile: &str, route_request: RouteRequest) -> Result<RouteResponse, OsrmError> {
+        self.engine(profile)?.route(route_request)
+    }
+
+    pub fn trip(&self, profile: &str, trip_request: TripRequest) -> Result<TripResponse, OsrmError> {
+        self.engine(profile)?.trip(trip_request)
+    }
+
+    pub fn nearest(&self, profile: &str, nearest_request: NearestRequest) -> Result<NearestResponse, OsrmError> {
+        self.engine(profile)?.nearest(nearest_request)
+    }
+
+    pub fn matching(&self, profile: &str, match_request: MatchRequest) -> Result<MatchResponse, OsrmError> {
+        self.engine(profile)?.matching(match_request)
+    }
+
+    pub fn tile(&self, profile: &str, tile_request: TileRequest) -> Result<Vec<u8>, OsrmError> {
+        self.engine(profile)?.tile(tile_request)
+    }
+
+    pub fn simple_route(&self, profile: &str, from: Point, to: Point) -> Result<SimpleRouteResponse, OsrmError> {
+        self.engine(profile)?.simple_route(from, to)
+    }
+
+    pub fn route_many(&self, profile: &str, route_requests: impl IntoIterator<Item = RouteRequest>) -> Result<Vec<Result<RouteResponse, OsrmError>>, OsrmError> {
+        Ok(self.engine(profile)?.route_many(route_requests))
+    }
+
+    pub fn simple_route_many(&self, profile: &str, pairs: impl IntoIterator<Item = (Point, Point)>) -> Result<Vec<Result<SimpleRouteResponse, OsrmError>>, OsrmError> {
+        Ok(self.engine(profile)?.simple_route_many(pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine_config::EngineConfigBuilder;
+
+    #[test]
+    fn it_routes_requests_by_profile_name() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = EngineConfigBuilder::default().path(path.clone()).algorithm(Algorithm::MLD).build().expect("Failed to build EngineConfig");
+        let van = ProfileConfigBuilder::default()
+            .name("van")
+            .engine(engine.clone())
+            .labels(HashMap::from([("capacity_kg".to_owned(), "800".to_owned())]))
+            .build()
+            .expect("Failed to build ProfileConfig");
+        let cargo_bike = ProfileConfigBuilder::default().name("cargo_bike").engine(engine).build().expect("Failed to build ProfileConfig");
+        let registry = OsrmRegistry::new(vec![van, cargo_bike]).expect("Failed to load the profiles");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        for profile in ["van", "cargo_bike"] {
+            let response = registry.simple_route(profile, paris.clone(), lyon.clone()).expect("route request failed");
+            assert_eq!(response.code, "Ok");
+        }
+        assert!(matches!(registry.simple_route("truck", paris, lyon), Err(OsrmError::UnknownProfile(_))));
+
+        let metadata = registry.metadata("van").expect("van should be registered");
+        assert_eq!(metadata.path, path);
+        assert_eq!(metadata.algorithm, Algorithm::MLD);
+        assert_eq!(metadata.labels.get("capacity_kg").map(String::as_str), Some("800"));
+    }
+
+    #[test]
+    fn it_rejects_duplicate_profiles() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let engine = EngineConfigBuilder::default().path(path).build().expect("Failed to build EngineConfig");
+        let car = ProfileConfigBuilder::default().name("car").engine(engine).build().expect("Failed to build ProfileConfig");
+
+        let result = OsrmRegistry::new(vec![car.clone(), car]);
+        assert!(matches!(result, Err(OsrmError::InvalidConfig(_))));
+    }
+}
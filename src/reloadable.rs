@@ -0,0 +1,155 @@
+//! Engine whose dataset can be replaced while it serves requests.
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use crate::engine_config::EngineConfig;
+use crate::errors::OsrmError;
+use crate::large_table::{LargeTableRequest, LargeTableResponse, TableProgress};
+use crate::matching::{MatchRequest, MatchResponse};
+use crate::nearest::{NearestRequest, NearestResponse};
+use crate::osrm_engine::OsrmEngine;
+use crate::point::Point;
+use crate::route::{RouteRequest, RouteResponse, SimpleRouteResponse};
+use crate::tables::{TableRequest, TableResponse};
+use crate::tile::TileRequest;
+use crate::traffic::{self, TrafficUpdate};
+use crate::trip::{TripRequest, TripResponse};
+
+/// Wraps an `OsrmEngine` that `reload` swaps atomically. Requests already running keep the
+/// previous engine, which is dropped once the last of them completes.
+pub struct ReloadableOsrmEngine {
+    current: RwLock<Arc<OsrmEngine>>,
+}
+
+impl ReloadableOsrmEngine {
+    pub fn new(config: EngineConfig) -> Result<Self, OsrmError> {
+        let engine = OsrmEngine::with_config(config)?;
+        Ok(ReloadableOsrmEngine {
+            current: RwLock::new(Arc::new(engine)),
+        })
+    }
+
+    /// Engine serving new requests. Holding it keeps its dataset loaded across reloads.
+    pub fn engine(&self) -> Arc<OsrmEngine> {
+        self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Loads the dataset again with the current configuration, e.g. after its files were replaced.
+    pub fn reload(&self) -> Result<(), OsrmError> {
+        let config = self.engine().config().clone();
+        self.reload_with(config)
+    }
+
+    /// Loads a dataset with another configuration. The current engine keeps serving until the
+    /// new one is ready, and is kept when loading fails.
+    pub fn reload_with(&self, config: EngineConfig) -> Result<(), OsrmError> {
+        let engine = Arc::new(OsrmEngine::with_config(config)?);
+        let previous = std::mem::replace(&mut *self.current.write().unwrap_or_else(PoisonError::into_inner), engine);
+        // Released outside of the lock, the engine itself goes away with its last request.
+        drop(previous);
+        Ok(())
+    }
+
+    /// Copies the dataset into `directory`, applies the traffic update to the copy, then serves
+    /// it like `reload_with`. The files of the current engine are never modified, so it keeps
+    /// serving during the update and is kept when any step fails. Removing the previous
+    /// directories is left to the caller, once their engines are no longer held.
+    pub fn update_traffic(&self, update: &TrafficUpdate, directory: &Path) -> Result<(), OsrmError> {
+        let config = self.engine().config().clone();
+        if config.use_shared_memory {
+            return Err(OsrmError::InvalidConfig(
+                "shared memory engines are updated with traffic::apply and datastore::load".to_owned(),
+            ));
+        }
+        let path = traffic::copy_dataset(&config.path, directory)?;
+        traffic::apply(&path, config.algorithm, update)?;
+        self.reload_with(EngineConfig { path, ..config })
+    }
+
+    pub fn table(&self, table_request: TableRequest) -> Result<TableResponse, OsrmError> {
+        self.engine().table(table_request)
+    }
+
+    pub fn large_table(&self, table_request: LargeTableRequest, progress: impl Fn(TableProgress) + Sync) -> Result<LargeTableResponse, OsrmError> {
+        self.engine().large_table(table_request, progress)
+    }
+
+    pub fn route(&self, route_request: RouteRequest) -> Result<RouteResponse, OsrmError> {
+        self.engine().route(route_request)
+    }
+
+    pub fn trip(&self, trip_request: TripRequest) -> Result<TripResponse, OsrmError> {
+        self.engine().trip(trip_request)
+    }
+
+    pub fn nearest(&self, nearest_request: NearestRequest) -> Result<NearestResponse, OsrmError> {
+        self.engine().nearest(nearest_request)
+    }
+
+    pub fn matching(&self, match_request: MatchRequest) -> Result<MatchResponse, OsrmError> {
+        self.engine().matching(match_request)
+    }
+
+    pub fn tile(&self, tile_request: TileRequest) -> Result<Vec<u8>, OsrmError> {
+        self.engine().tile(tile_request)
+    }
+
+    pub fn simple_route(&self, from: Point, to: Point) -> Result<SimpleRouteResponse, OsrmError> {
+        self.engine().simple_route(from, to)
+    }
+
+    pub fn route_many(&self, route_requests: impl IntoIterator<Item = RouteRequest>) -> Vec<Result<RouteResponse, OsrmError>> {
+        self.engine().route_many(route_requests)
+    }
+
+    pub fn simple_route_many(&self, pairs: impl IntoIterator<Item = (Point, Point)>) -> Vec<Result<SimpleRouteResponse, OsrmError>> {
+        self.engine().simple_route_many(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::engine_config::EngineConfigBuilder;
+
+    #[test]
+    fn it_reloads_a_dataset_while_serving_requests() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let config = EngineConfigBuilder::default().path(path).algorithm(Algorithm::MLD).build().expect("Failed to build EngineConfig");
+        let engine = ReloadableOsrmEngine::new(config).expect("Failed to initialize OSRM engine");
+
+        let paris = Point { longitude: 2.3522, latitude: 48.8566, ..Default::default() };
+        let lyon = Point { longitude: 4.8357, latitude: 45.7640, ..Default::default() };
+        let previous = engine.engine();
+
+        std::thread::scope(|scope| {
+            let requests = scope.spawn(|| {
+                for _ in 0..20 {
+                    engine.simple_route(paris.clone(), lyon.clone()).expect("route request failed");
+                }
+            });
+            engine.reload().expect("Reload failed");
+            requests.join().expect("Requests panicked");
+        });
+
+        assert!(!Arc::ptr_eq(&previous, &engine.engine()), "A new engine should serve new requests");
+        let response = previous.simple_route(paris, lyon).expect("The previous engine should still answer");
+        assert_eq!(response.code, "Ok");
+    }
+
+    #[test]
+    fn it_keeps_the_current_dataset_when_a_reload_fails() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let path = std::env::var("OSRM_TEST_DATA_PATH_MLD")
+            .expect("Environment variable OSRM_TEST_DATA_PATH_MLD must be defined with a french map");
+        let config = EngineConfigBuilder::default().path(path).algorithm(Algorithm::MLD).build().expect("Failed to build EngineConfig");
+        let engine = ReloadableOsrmEngine::new(config.clone()).expect("Failed to initialize OSRM engine");
+        let current = engine.engine();
+
+        let missing = EngineConfig { path: "/does/not/exist.osrm".to_owned(), ..config };
+        assert!(engine.reload_with(missing).is_err());
+        assert!(Arc::ptr_eq(&current, &engine.engine()), "The current engine should be kept");
+    }
+}
@@ -2,9 +2,41 @@ use derive_builder::Builder;
 use crate::point::Point;
 use serde::{Deserialize, Serialize};
 use crate::waypoints::Waypoint;
+use crate::options::{annotations_mask, Alternatives, AnnotationType, Geometries, Overview};
+use crate::OsrmRouteOptions;
+
 #[derive(Debug, Builder, Clone)]
 pub struct RouteRequest {
     pub points : Vec<Point>,
+    #[builder(default, setter(into))]
+    pub alternatives: Alternatives,
+    #[builder(default)]
+    pub steps: bool,
+    #[builder(default)]
+    pub annotations: Vec<AnnotationType>,
+    #[builder(default)]
+    pub overview: Overview,
+    #[builder(default)]
+    pub geometries: Geometries,
+    #[builder(default, setter(strip_option))]
+    pub continue_straight: Option<bool>,
+}
+
+impl RouteRequest {
+    pub(crate) fn options(&self) -> OsrmRouteOptions {
+        OsrmRouteOptions {
+            steps: self.steps,
+            alternatives: self.alternatives.count(),
+            annotations: annotations_mask(&self.annotations),
+            overview: self.overview.as_raw(),
+            geometries: self.geometries.as_raw(),
+            continue_straight: match self.continue_straight {
+                None => -1,
+                Some(false) => 0,
+                Some(true) => 1,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,9 +66,26 @@ pub struct OsrmResponse {
 pub struct Route {
     pub legs: Vec<Leg>,
     pub weight_name: String,
-    pub geometry: String,
+    /// Absent when the request uses `Overview::False`.
+    pub geometry: Option<Geometry>,
     pub weight: f64,
     pub duration: f64,
+    pub distance: f64,
+}
+
+/// A geometry encoded according to the requested `Geometries`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Geometry {
+    Encoded(String),
+    GeoJson(LineString),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LineString {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub coordinates: Vec<[f64; 2]>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -46,8 +95,155 @@ pub struct Leg {
     pub summary: String,
     pub duration: f64,
     pub distance: f64,
+    /// Only present when `RouteRequest::annotations` is not empty.
+    pub annotation: Option<Annotation>,
+}
+
+/// Per-segment metadata of a leg. Each list only holds values for the requested `AnnotationType`s.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Annotation {
+    pub distance: Option<Vec<f64>>,
+    pub duration: Option<Vec<f64>>,
+    pub speed: Option<Vec<f64>>,
+    pub weight: Option<Vec<f64>>,
+    /// OSM node ids along the leg, one more than the number of segments.
+    pub nodes: Option<Vec<u64>>,
+    /// Index into `AnnotationMetadata::datasource_names` for each segment.
+    pub datasources: Option<Vec<u32>>,
+    pub metadata: Option<AnnotationMetadata>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AnnotationMetadata {
+    pub datasource_names: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Step {
-}
\ No newline at end of file
+    pub distance: f64,
+    pub duration: f64,
+    pub weight: f64,
+    pub name: String,
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    pub pronunciation: Option<String>,
+    pub destinations: Option<String>,
+    pub exits: Option<String>,
+    pub mode: String,
+    pub driving_side: DrivingSide,
+    pub rotary_name: Option<String>,
+    pub rotary_pronunciation: Option<String>,
+    pub geometry: Geometry,
+    pub maneuver: StepManeuver,
+    pub intersections: Vec<Intersection>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DrivingSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StepManeuver {
+    pub location: [f64; 2],
+    pub bearing_before: u16,
+    pub bearing_after: u16,
+    #[serde(rename = "type")]
+    pub maneuver_type: ManeuverType,
+    pub modifier: Option<ManeuverModifier>,
+    /// Exit number to take, only set for roundabouts and rotaries.
+    pub exit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManeuverType {
+    Turn,
+    #[serde(rename = "new name")]
+    NewName,
+    Depart,
+    Arrive,
+    Merge,
+    Ramp,
+    #[serde(rename = "on ramp")]
+    OnRamp,
+    #[serde(rename = "off ramp")]
+    OffRamp,
+    Fork,
+    #[serde(rename = "end of road")]
+    EndOfRoad,
+    #[serde(rename = "use lane")]
+    UseLane,
+    Continue,
+    Roundabout,
+    Rotary,
+    #[serde(rename = "roundabout turn")]
+    RoundaboutTurn,
+    Notification,
+    #[serde(rename = "exit roundabout")]
+    ExitRoundabout,
+    #[serde(rename = "exit rotary")]
+    ExitRotary,
+    /// Any maneuver type introduced by a newer OSRM version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManeuverModifier {
+    #[serde(rename = "uturn")]
+    UTurn,
+    #[serde(rename = "sharp right")]
+    SharpRight,
+    Right,
+    #[serde(rename = "slight right")]
+    SlightRight,
+    Straight,
+    #[serde(rename = "slight left")]
+    SlightLeft,
+    Left,
+    #[serde(rename = "sharp left")]
+    SharpLeft,
+    /// Any modifier introduced by a newer OSRM version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Intersection {
+    pub location: [f64; 2],
+    pub bearings: Vec<u16>,
+    pub entry: Vec<bool>,
+    /// Index into `bearings` of the road used to enter the intersection, absent on departure.
+    #[serde(rename = "in")]
+    pub in_bearing: Option<usize>,
+    /// Index into `bearings` of the road used to leave the intersection, absent on arrival.
+    #[serde(rename = "out")]
+    pub out_bearing: Option<usize>,
+    pub lanes: Option<Vec<Lane>>,
+    pub classes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Lane {
+    /// Turn indications painted on the lane, e.g. `"left"` or `"straight"`.
+    pub indications: Vec<String>,
+    pub valid: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_deserializes_unknown_maneuvers() {
+        let json = r#"{"location":[2.3522,48.8566],"bearing_before":0,"bearing_after":90,"type":"hover","modifier":"upward"}"#;
+        let maneuver: StepManeuver = serde_json::from_str(json).expect("Failed to parse the maneuver");
+
+        assert_eq!(maneuver.maneuver_type, ManeuverType::Unknown);
+        assert_eq!(maneuver.modifier, Some(ManeuverModifier::Unknown));
+    }
+}
@@ -1,17 +1,46 @@
 use derive_builder::Builder;
-use serde::Deserialize;
+use crate::errors::OsrmError;
 pub(crate) use crate::point::Point;
+use crate::{OsrmTableOptions, TableResult, TableWaypoint};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 #[allow(dead_code)]
 pub struct TableResponse {
     pub code: String,
     pub destinations: Vec<TableLocationEntry>,
+    /// Empty unless `TableAnnotation::Duration` was requested.
     pub durations: Vec<Vec<Option<f64>>>,
+    /// Only present when `TableAnnotation::Distance` was requested.
+    pub distances: Option<Vec<Vec<Option<f64>>>>,
+    /// `[row, column]` of the cells estimated with `fallback_speed` instead of routed.
+    pub fallback_speed_cells: Option<Vec<[usize; 2]>>,
     sources: Vec<TableLocationEntry>,
 }
 
-#[derive(Debug, Deserialize)]
+impl TableResponse {
+    /// Builds the response from the buffers filled by the wrapper.
+    pub(crate) fn from_result(result: TableResult) -> Self {
+        let columns = result.columns;
+        let to_matrix = |values: Vec<f64>| -> Vec<Vec<Option<f64>>> {
+            values
+                .chunks(columns.max(1))
+                .take(result.rows)
+                .map(|row| row.iter().map(|value| (!value.is_nan()).then_some(*value)).collect())
+                .collect()
+        };
+
+        TableResponse {
+            code: "Ok".to_owned(),
+            destinations: result.destinations.into_iter().map(TableLocationEntry::from).collect(),
+            durations: result.durations.map(to_matrix).unwrap_or_default(),
+            distances: result.distances.map(to_matrix),
+            fallback_speed_cells: (!result.fallback_speed_cells.is_empty()).then_some(result.fallback_speed_cells),
+            sources: result.sources.into_iter().map(TableLocationEntry::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
 #[allow(dead_code)]
 pub struct TableLocationEntry {
     hint: String,
@@ -20,8 +49,141 @@ pub struct TableLocationEntry {
     distance: f64,
 }
 
+impl From<TableWaypoint> for TableLocationEntry {
+    fn from(waypoint: TableWaypoint) -> Self {
+        TableLocationEntry {
+            hint: waypoint.hint,
+            location: waypoint.location,
+            name: waypoint.name,
+            distance: waypoint.distance,
+        }
+    }
+}
+
+/// Either `sources` and `destinations`, or a single `points` list whose rows and columns are
+/// selected by index, so that points used on both sides are only snapped once.
 #[derive(Debug, Builder, Clone)]
 pub struct TableRequest{
+    #[builder(default)]
     pub sources: Vec<Point>,
-    pub destinations: Vec<Point>
+    #[builder(default)]
+    pub destinations: Vec<Point>,
+    /// Coordinates of the matrix, used instead of `sources` and `destinations`.
+    #[builder(default)]
+    pub points: Vec<Point>,
+    /// Indices in `points` of the rows, every point when `None`.
+    #[builder(default, setter(strip_option))]
+    pub source_indices: Option<Vec<usize>>,
+    /// Indices in `points` of the columns, every point when `None`.
+    #[builder(default, setter(strip_option))]
+    pub destination_indices: Option<Vec<usize>>,
+    /// Matrices to compute, durations only by default.
+    #[builder(default = "vec![TableAnnotation::Duration]")]
+    pub annotations: Vec<TableAnnotation>,
+    /// Speed in km/h used to estimate the cells for which no route was found.
+    #[builder(default, setter(strip_option))]
+    pub fallback_speed: Option<f64>,
+    /// Coordinates used for the crow-fly estimate of `fallback_speed`.
+    #[builder(default)]
+    pub fallback_coordinate: FallbackCoordinate,
+    /// Multiplies every duration of the matrix.
+    #[builder(default = "1.0")]
+    pub scale_factor: f64,
+}
+
+impl TableRequest {
+    pub(crate) fn into_coordinates(self) -> Result<TableCoordinates, OsrmError> {
+        if self.points.is_empty() {
+            if self.sources.is_empty() || self.destinations.is_empty() {
+                return Err(OsrmError::InvalidTableArgument);
+            }
+            if self.source_indices.is_some() || self.destination_indices.is_some() {
+                return Err(OsrmError::InvalidTableArgument);
+            }
+            let len_sources = self.sources.len();
+            let sources_index = (0..len_sources).collect();
+            let destinations_index = (len_sources..len_sources + self.destinations.len()).collect();
+            return Ok(TableCoordinates {
+                points: [self.sources, self.destinations].concat(),
+                sources: sources_index,
+                destinations: destinations_index,
+            });
+        }
+
+        if !self.sources.is_empty() || !self.destinations.is_empty() {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        let len = self.points.len();
+        let is_valid = |indices: &Option<Vec<usize>>| {
+            indices.as_ref().is_none_or(|indices| !indices.is_empty() && indices.iter().all(|index| *index < len))
+        };
+        if !is_valid(&self.source_indices) || !is_valid(&self.destination_indices) {
+            return Err(OsrmError::InvalidTableArgument);
+        }
+        Ok(TableCoordinates {
+            points: self.points,
+            sources: self.source_indices.unwrap_or_default(),
+            destinations: self.destination_indices.unwrap_or_default(),
+        })
+    }
+
+    pub(crate) fn options(&self) -> OsrmTableOptions {
+        table_options(&self.annotations, self.fallback_speed, self.fallback_coordinate, self.scale_factor)
+    }
+}
+
+pub(crate) fn table_options(
+    annotations: &[TableAnnotation],
+    fallback_speed: Option<f64>,
+    fallback_coordinate: FallbackCoordinate,
+    scale_factor: f64,
+) -> OsrmTableOptions {
+    OsrmTableOptions {
+        annotations: annotations.iter().fold(0, |mask, annotation| mask | annotation.mask()),
+        has_fallback_speed: fallback_speed.is_some(),
+        fallback_speed: fallback_speed.unwrap_or(0.0),
+        fallback_coordinate: fallback_coordinate.as_raw(),
+        scale_factor,
+    }
+}
+
+/// Coordinates sent to OSRM with the indices of the sources and destinations among them,
+/// an empty list of indices selecting every coordinate.
+#[derive(Debug)]
+pub(crate) struct TableCoordinates {
+    pub(crate) points: Vec<Point>,
+    pub(crate) sources: Vec<usize>,
+    pub(crate) destinations: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FallbackCoordinate {
+    #[default]
+    Input,
+    Snapped,
+}
+
+impl FallbackCoordinate {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            FallbackCoordinate::Input => 0,
+            FallbackCoordinate::Snapped => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableAnnotation {
+    Duration,
+    Distance,
+}
+
+impl TableAnnotation {
+    /// Bit used by `osrm::TableParameters::AnnotationsType`.
+    pub(crate) fn mask(&self) -> u32 {
+        match self {
+            TableAnnotation::Duration => 0x01,
+            TableAnnotation::Distance => 0x02,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,9 @@
+use derive_builder::Builder;
+
+/// A tile in the XYZ scheme. OSRM only serves zoom levels 12 and above.
+#[derive(Debug, Builder, Clone, Copy)]
+pub struct TileRequest {
+    pub x : u32,
+    pub y : u32,
+    pub z : u32,
+}
@@ -0,0 +1,242 @@
+//! Live traffic updates: writes current speeds in OSRM's CSV lookup format and recomputes the
+//! dataset weights with them, like `osrm-customize`/`osrm-contract` with `--segment-speed-file`
+//! and `--turn-penalty-file`.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use derive_builder::Builder;
+use crate::algorithm::Algorithm;
+use crate::errors::OsrmError;
+use crate::pipeline::{self, PipelineConfig};
+
+/// Speed of the road segment between two consecutive OSM nodes, in that direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentSpeed {
+    pub from_osm_node: u64,
+    pub to_osm_node: u64,
+    /// A speed of 0 closes the segment.
+    pub speed_kmh: f64,
+}
+
+/// Penalty of the turn from `from_osm_node` to `to_osm_node` through `via_osm_node`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurnPenalty {
+    pub from_osm_node: u64,
+    pub via_osm_node: u64,
+    pub to_osm_node: u64,
+    pub penalty_seconds: f64,
+}
+
+#[derive(Debug, Clone, Default, Builder)]
+pub struct TrafficUpdate {
+    #[builder(default)]
+    pub segment_speeds: Vec<SegmentSpeed>,
+    #[builder(default)]
+    pub turn_penalties: Vec<TurnPenalty>,
+    /// Every available core when `None`.
+    #[builder(default, setter(strip_option))]
+    pub threads: Option<usize>,
+}
+
+impl TrafficUpdate {
+    fn validate(&self) -> Result<(), OsrmError> {
+        if self.segment_speeds.iter().any(|speed| !speed.speed_kmh.is_finite() || speed.speed_kmh < 0.0) {
+            return Err(OsrmError::InvalidConfig("speed_kmh must be a finite, non-negative number".to_owned()));
+        }
+        if self.turn_penalties.iter().any(|penalty| !penalty.penalty_seconds.is_finite()) {
+            return Err(OsrmError::InvalidConfig("penalty_seconds must be a finite number".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// Writes segment speeds as `from_osm_node,to_osm_node,speed_kmh` lines.
+pub fn write_segment_speeds(path: &Path, speeds: &[SegmentSpeed]) -> Result<(), OsrmError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for speed in speeds {
+        writeln!(writer, "{},{},{}", speed.from_osm_node, speed.to_osm_node, speed.speed_kmh)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes turn penalties as `from_osm_node,via_osm_node,to_osm_node,penalty_seconds` lines.
+pub fn write_turn_penalties(path: &Path, penalties: &[TurnPenalty]) -> Result<(), OsrmError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for penalty in penalties {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            penalty.from_osm_node, penalty.via_osm_node, penalty.to_osm_node, penalty.penalty_seconds
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Copies the files of the `.osrm` dataset at `path` into `directory` and returns the path of
+/// the copy. `directory` is created if needed and must not be the directory of the dataset.
+pub fn copy_dataset(path: &str, directory: &Path) -> Result<String, OsrmError> {
+    let source = Path::new(path);
+    let (Some(parent), Some(name)) = (source.parent(), source.file_name().and_then(|name| name.to_str())) else {
+        return Err(OsrmError::InvalidPath(path.to_owned()));
+    };
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    std::fs::create_dir_all(directory)?;
+    if parent.canonicalize()? == directory.canonicalize()? {
+        return Err(OsrmError::InvalidPath(format!("{} already holds the dataset", directory.display())));
+    }
+
+    // A dataset is the `.osrm` file, if any, and every `.osrm.*` file next to it.
+    let prefix = format!("{}.", name);
+    let mut copied = false;
+    for entry in std::fs::read_dir(parent)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else { continue };
+        if (file_name == name || file_name.starts_with(&prefix)) && entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), directory.join(file_name))?;
+            copied = true;
+        }
+    }
+    if !copied {
+        return Err(OsrmError::InvalidPath(path.to_owned()));
+    }
+    Ok(directory.join(name).to_string_lossy().into_owned())
+}
+
+/// Recomputes the weights of the `.osrm` dataset at `path` with the update, running `customize`
+/// for MLD or `contract` for CH. The CSV files are written to a temporary directory, so they
+/// never end up among the dataset files, and removed once the weights are recomputed.
+///
+/// The files are rewritten in place, so no engine may serve them from files meanwhile: update a
+/// copy instead, see `ReloadableOsrmEngine::update_traffic`. Engines using shared memory do not
+/// read the files and pick the new weights up once the dataset is loaded again with
+/// `datastore::load` and `only_metric`.
+pub fn apply(path: &str, algorithm: Algorithm, update: &TrafficUpdate) -> Result<(), OsrmError> {
+    if path.is_empty() {
+        return Err(OsrmError::InvalidPath(path.to_owned()));
+    }
+    update.validate()?;
+
+    // Unique per call, several datasets may be updated at once.
+    static UPDATES: AtomicUsize = AtomicUsize::new(0);
+    let directory = std::env::temp_dir().join(format!(
+        "osrm-binding-traffic-{}-{}",
+        std::process::id(),
+        UPDATES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&directory)?;
+    let result = recompute(path, algorithm, update, &directory);
+    let _ = std::fs::remove_dir_all(&directory);
+    result
+}
+
+fn recompute(path: &str, algorithm: Algorithm, update: &TrafficUpdate, directory: &Path) -> Result<(), OsrmError> {
+    let mut config = PipelineConfig {
+        path: path.to_owned(),
+        threads: update.threads,
+        segment_speed_files: Vec::new(),
+        turn_penalty_files: Vec::new(),
+    };
+    if !update.segment_speeds.is_empty() {
+        let file = directory.join("segment-speeds.csv");
+        write_segment_speeds(&file, &update.segment_speeds)?;
+        config.segment_speed_files.push(file.to_string_lossy().into_owned());
+    }
+    if !update.turn_penalties.is_empty() {
+        let file = directory.join("turn-penalties.csv");
+        write_turn_penalties(&file, &update.turn_penalties)?;
+        config.turn_penalty_files.push(file.to_string_lossy().into_owned());
+    }
+
+    match algorithm {
+        Algorithm::MLD => pipeline::customize(config),
+        Algorithm::CH => pipeline::contract(config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine_config::EngineConfigBuilder;
+    use crate::options::AnnotationType;
+    use crate::pipeline::ExtractConfigBuilder;
+    use crate::point::Point;
+    use crate::reloadable::ReloadableOsrmEngine;
+    use crate::route::RouteRequestBuilder;
+
+    #[test]
+    fn it_writes_segment_speeds_in_osrm_format() {
+        let path = std::env::temp_dir().join(format!("osrm-binding-speeds-{}.csv", std::process::id()));
+        let speeds = [
+            SegmentSpeed { from_osm_node: 1, to_osm_node: 2, speed_kmh: 30.0 },
+            SegmentSpeed { from_osm_node: 2, to_osm_node: 1, speed_kmh: 12.5 },
+        ];
+        write_segment_speeds(&path, &speeds).expect("Failed to write the segment speeds");
+
+        let content = std::fs::read_to_string(&path).expect("Failed to read the segment speeds");
+        std::fs::remove_file(&path).expect("Failed to remove the segment speeds");
+        assert_eq!(content, "1,2,30\n2,1,12.5\n");
+    }
+
+    #[test]
+    fn it_slows_down_a_route_after_a_traffic_update() {
+        dotenvy::dotenv().expect(".env file could not be read");
+        let pbf = std::env::var("OSRM_TEST_PBF_PATH")
+            .expect("Environment variable OSRM_TEST_PBF_PATH must be defined with a small .osm.pbf extract");
+        let profile = std::env::var("OSRM_TEST_PROFILE_PATH")
+            .expect("Environment variable OSRM_TEST_PROFILE_PATH must be defined with a Lua profile");
+        let output = std::env::temp_dir().join(format!("osrm-binding-traffic-{}", std::process::id()));
+        std::fs::create_dir_all(&output).expect("Failed to create the output directory");
+        let path = output.join("extract.osrm").to_string_lossy().into_owned();
+
+        let config = ExtractConfigBuilder::default()
+            .input_path(pbf)
+            .profile_path(profile)
+            .output_path(path.clone())
+            .build()
+            .expect("Failed to build ExtractConfig");
+        pipeline::extract(config).expect("extract failed");
+        let config = PipelineConfig { path: path.clone(), threads: None, segment_speed_files: Vec::new(), turn_penalty_files: Vec::new() };
+        pipeline::partition(config.clone()).expect("partition failed");
+        pipeline::customize(config).expect("customize failed");
+
+        let config = EngineConfigBuilder::default().path(path.clone()).algorithm(Algorithm::MLD).build().expect("Failed to build EngineConfig");
+        let engine = ReloadableOsrmEngine::new(config).expect("Failed to initialize OSRM engine");
+        let previous = engine.engine();
+        let request = RouteRequestBuilder::default()
+            .points(vec![
+                Point { longitude: 7.4281, latitude: 43.7396, ..Default::default() }, // Monte-Carlo
+                Point { longitude: 7.4167, latitude: 43.7311, ..Default::default() }, // Fontvieille
+            ])
+            .annotations(vec![AnnotationType::Nodes])
+            .build()
+            .expect("Failed to build RouteRequest");
+        let before = engine.route(request.clone()).expect("route request failed");
+        let nodes = before.routes[0].legs[0].annotation.as_ref().and_then(|a| a.nodes.clone()).expect("Nodes should be returned");
+
+        let update = TrafficUpdateBuilder::default()
+            .segment_speeds(nodes.windows(2).map(|pair| SegmentSpeed { from_osm_node: pair[0], to_osm_node: pair[1], speed_kmh: 1.0 }).collect())
+            .build()
+            .expect("Failed to build TrafficUpdate");
+        let updated = output.join("updated");
+        engine.update_traffic(&update, &updated).expect("Traffic update failed");
+        let after = engine.route(request.clone()).expect("route request failed");
+
+        assert!(after.routes[0].duration > before.routes[0].duration, "The congested route should be slower");
+        assert_eq!(engine.engine().config().path, updated.join("extract.osrm").to_string_lossy());
+        for entry in std::fs::read_dir(&updated).expect("Failed to read the updated directory") {
+            let name = entry.expect("Failed to read a directory entry").file_name().to_string_lossy().into_owned();
+            assert!(name.starts_with("extract.osrm") && !name.ends_with(".csv"), "Unexpected file {} in the copy", name);
+        }
+        let unchanged = previous.route(request).expect("The previous engine should still answer");
+        assert_eq!(unchanged.routes[0].duration, before.routes[0].duration, "The previous dataset should be untouched");
+        assert!(matches!(engine.update_traffic(&update, &updated), Err(OsrmError::InvalidPath(_))), "The served directory should be rejected");
+
+        drop(previous);
+        drop(engine);
+        std::fs::remove_dir_all(&output).expect("Failed to remove the output directory");
+    }
+}
@@ -1,14 +1,94 @@
 use derive_builder::Builder;
 use serde::Deserialize;
+use crate::options::{annotations_mask, AnnotationType, Geometries, Overview};
 use crate::point::Point;
+use crate::route::Route;
+use crate::waypoints::Waypoint;
+use crate::OsrmRouteOptions;
 
-#[derive(Debug, Builder)]
+#[derive(Debug, Builder, Clone)]
 pub struct TripRequest {
     pub points : Vec<Point>,
+    /// Returns to the first location when `true`.
+    #[builder(default = "true")]
+    pub roundtrip : bool,
+    #[builder(default)]
+    pub source : TripSource,
+    #[builder(default)]
+    pub destination : TripDestination,
+    #[builder(default)]
+    pub steps: bool,
+    #[builder(default)]
+    pub annotations: Vec<AnnotationType>,
+    #[builder(default)]
+    pub overview: Overview,
+    #[builder(default)]
+    pub geometries: Geometries,
+}
+
+impl TripRequest {
+    pub(crate) fn options(&self) -> OsrmRouteOptions {
+        OsrmRouteOptions {
+            steps: self.steps,
+            alternatives: 0,
+            annotations: annotations_mask(&self.annotations),
+            overview: self.overview.as_raw(),
+            geometries: self.geometries.as_raw(),
+            continue_straight: -1,
+        }
+    }
+}
+
+/// Where the trip starts. OSRM only supports open trips (`roundtrip = false`)
+/// that start at the first point and end at the last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TripSource {
+    #[default]
+    Any,
+    First,
+}
+
+impl TripSource {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            TripSource::Any => 0,
+            TripSource::First => 1,
+        }
+    }
+}
+
+/// Where the trip ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TripDestination {
+    #[default]
+    Any,
+    Last,
+}
+
+impl TripDestination {
+    pub(crate) fn as_raw(&self) -> i32 {
+        match self {
+            TripDestination::Any => 0,
+            TripDestination::Last => 1,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct TripResponse {
+    pub code: String,
+    pub trips: Vec<Route>,
+    /// Input points in their original order.
+    pub waypoints: Vec<TripWaypoint>,
+}
 
-}
\ No newline at end of file
+#[derive(Debug, Deserialize)]
+pub struct TripWaypoint {
+    #[serde(flatten)]
+    pub waypoint: Waypoint,
+    /// Index of the trip this point belongs to.
+    pub trips_index: usize,
+    /// Position of the point inside its trip.
+    pub waypoint_index: usize,
+}
@@ -6,4 +6,6 @@ pub struct Waypoint {
     pub location: [f64; 2],
     pub name: String,
     pub distance: f64,
+    /// OSM ids of the nodes of the snapped segment, only returned by the nearest service.
+    pub nodes: Option<[u64; 2]>,
 }
\ No newline at end of file
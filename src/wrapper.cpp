@@ -6,23 +6,474 @@
 #include <util/json_renderer.hpp>
 #include <osrm/route_parameters.hpp>
 #include <osrm/trip_parameters.hpp>
+#include <osrm/nearest_parameters.hpp>
+#include <osrm/match_parameters.hpp>
+#include <osrm/tile_parameters.hpp>
+#include <osrm/extractor.hpp>
+#include <osrm/extractor_config.hpp>
+#include <osrm/partitioner.hpp>
+#include <osrm/partitioner_config.hpp>
+#include <osrm/customizer.hpp>
+#include <osrm/customizer_config.hpp>
+#include <osrm/contractor.hpp>
+#include <osrm/contractor_config.hpp>
+#include <engine/api/base_result.hpp>
+#include <engine/api/flatbuffers/fbresult_generated.h>
+#include <flatbuffers/flatbuffers.h>
+#include <storage/shared_datatype.hpp>
+#include <storage/shared_memory.hpp>
+#include <storage/shared_monitor.hpp>
+#include <storage/storage.hpp>
+#include <storage/storage_config.hpp>
+#include <engine/api/base_parameters.hpp>
+#include <engine/approach.hpp>
+#include <engine/bearing.hpp>
+#include <engine/hint.hpp>
 
 #include <string>
-#include <iostream>
 #include <cstdlib>
+#include <cstring>
+#include <iterator>
+#include <limits>
+#include <mutex>
+#include <new>
+#include <thread>
+#include <unordered_map>
+#include <vector>
+
+#ifndef OSRM_BINDING_VERSION_MAJOR
+#define OSRM_BINDING_VERSION_MAJOR 6
+#endif
+
+// OSRM 6 replaced boost::optional and mapbox::util::variant by their std counterparts.
+#if OSRM_BINDING_VERSION_MAJOR < 6
+#include <boost/optional.hpp>
+
+template <typename T> using osrm_optional = boost::optional<T>;
+static const auto osrm_nullopt = boost::none;
+
+template <typename T, typename Variant> static const T& variant_get(const Variant& value) {
+    return value.template get<T>();
+}
+
+template <typename T, typename Variant> static const T* variant_get_if(const Variant* value) {
+    return value->template is<T>() ? &value->template get<T>() : nullptr;
+}
+#else
+#include <optional>
+#include <variant>
+
+template <typename T> using osrm_optional = std::optional<T>;
+static constexpr auto osrm_nullopt = std::nullopt;
+
+template <typename T, typename Variant> static const T& variant_get(const Variant& value) {
+    return std::get<T>(value);
+}
+
+template <typename T, typename Variant> static const T* variant_get_if(const Variant* value) {
+    return std::get_if<T>(value);
+}
+#endif
+
+
+struct OSRM_Result {
+    int code;
+    char* message;
+};
+
+struct OSRM_Buffer {
+    int code;
+    char* data;
+    size_t length;
+};
+
+struct OSRM_Coordinate {
+    double longitude;
+    double latitude;
+    bool has_bearing;
+    short bearing;
+    short bearing_range;
+    bool has_radius;
+    double radius;
+    const char* hint;
+    int approach;
+};
+
+// Negative values keep OSRM's default.
+struct OSRM_EngineConfig {
+    int max_locations_trip;
+    int max_locations_viaroute;
+    int max_locations_distance_table;
+    int max_locations_map_matching;
+    double max_radius_map_matching;
+    int max_results_nearest;
+    int max_alternatives;
+    double default_radius;
+    int use_mmap;
+    bool use_shared_memory;
+    const char* dataset_name;
+};
+
+struct OSRM_TableOptions {
+    unsigned int annotations;
+    bool has_fallback_speed;
+    double fallback_speed;
+    int fallback_coordinate;
+    double scale_factor;
+};
+
+// Location entry of a table source or destination, the strings are owned by the caller.
+struct OSRM_TableWaypoint {
+    double longitude;
+    double latitude;
+    double distance;
+    char* name;
+    char* hint;
+};
+
+// Caller allocated outputs of `osrm_table`. Matrices are row-major with NaN for unreachable
+// cells and are only written when non-null.
+struct OSRM_TableBuffers {
+    double* durations;
+    double* distances;
+    OSRM_TableWaypoint* sources;
+    OSRM_TableWaypoint* destinations;
+    size_t* fallback_speed_cells;
+    size_t num_fallback_speed_cells;
+};
+
+struct OSRM_RouteOptions {
+    bool steps;
+    unsigned int alternatives;
+    unsigned int annotations;
+    int overview;
+    int geometries;
+    int continue_straight;
+};
+
+struct OSRM_ExtractConfig {
+    const char* input_path;
+    const char* profile_path;
+    const char* output_path;
+    int threads;
+};
+
+// Configuration shared by partition, customize and contract. The lookup files are only used
+// by customize and contract.
+struct OSRM_PipelineConfig {
+    const char* base_path;
+    int threads;
+    const char* const* segment_speed_files;
+    size_t num_segment_speed_files;
+    const char* const* turn_penalty_files;
+    size_t num_turn_penalty_files;
+};
+
+static void apply_coordinates(osrm::engine::api::BaseParameters& params,
+                              const OSRM_Coordinate* coordinates,
+                              size_t num_coordinates) {
+    bool any_bearing = false, any_radius = false, any_hint = false, any_approach = false;
+
+    for (size_t i = 0; i < num_coordinates; ++i) {
+        const auto& coordinate = coordinates[i];
+        params.coordinates.push_back({
+            osrm::util::FloatLongitude{coordinate.longitude},
+            osrm::util::FloatLatitude{coordinate.latitude}
+        });
+        any_bearing |= coordinate.has_bearing;
+        any_radius |= coordinate.has_radius;
+        any_hint |= coordinate.hint != nullptr;
+        any_approach |= coordinate.approach >= 0;
+    }
 
-extern "C" {
+    // OSRM expects these lists to be either empty or as long as the coordinates list.
+    for (size_t i = 0; i < num_coordinates; ++i) {
+        const auto& coordinate = coordinates[i];
+        if (any_bearing) {
+            params.bearings.push_back(coordinate.has_bearing
+                ? osrm_optional<osrm::engine::Bearing>{osrm::engine::Bearing{coordinate.bearing, coordinate.bearing_range}}
+                : osrm_nullopt);
+        }
+        if (any_radius) {
+            params.radiuses.push_back(coordinate.has_radius ? osrm_optional<double>{coordinate.radius} : osrm_nullopt);
+        }
+        if (any_hint) {
+            params.hints.push_back(coordinate.hint
+                ? osrm_optional<osrm::engine::Hint>{osrm::engine::Hint::FromBase64(coordinate.hint)}
+                : osrm_nullopt);
+        }
+        if (any_approach) {
+            osrm_optional<osrm::engine::Approach> approach;
+            if (coordinate.approach == 0) {
+                approach = osrm::engine::Approach::UNRESTRICTED;
+            } else if (coordinate.approach == 1) {
+                approach = osrm::engine::Approach::CURB;
+            }
+#if OSRM_BINDING_VERSION_MAJOR >= 6
+            else if (coordinate.approach == 2) {
+                approach = osrm::engine::Approach::OPPOSITE;
+            }
+#endif
+            params.approaches.push_back(approach);
+        }
+    }
+}
+
+static void apply_route_options(osrm::RouteParameters& params, const OSRM_RouteOptions* options) {
+    if (!options) {
+        return;
+    }
+
+    params.steps = options->steps;
+    params.alternatives = options->alternatives > 0;
+    params.number_of_alternatives = options->alternatives;
+
+    params.annotations = options->annotations != 0;
+    params.annotations_type = static_cast<osrm::RouteParameters::AnnotationsType>(options->annotations);
+
+    switch (options->overview) {
+        case 1: params.overview = osrm::RouteParameters::OverviewType::Full; break;
+        case 2: params.overview = osrm::RouteParameters::OverviewType::False; break;
+        default: params.overview = osrm::RouteParameters::OverviewType::Simplified; break;
+    }
+
+    switch (options->geometries) {
+        case 1: params.geometries = osrm::RouteParameters::GeometriesType::Polyline6; break;
+        case 2: params.geometries = osrm::RouteParameters::GeometriesType::GeoJSON; break;
+        default: params.geometries = osrm::RouteParameters::GeometriesType::Polyline; break;
+    }
+
+    if (options->continue_straight >= 0) {
+        params.continue_straight = options->continue_straight == 1;
+    }
+}
+
+static char* copy_string(const std::string& value) {
+    char* copy = new char[value.length() + 1];
+    strcpy(copy, value.c_str());
+    return copy;
+}
+
+// Returned when an error message cannot be allocated, never released by `osrm_free_string`.
+static char ALLOCATION_FAILED[] = "Failed to allocate the error message";
+
+// Copies an error message without throwing, safe to call from `catch` blocks.
+static char* copy_message(const char* message) noexcept {
+    const size_t length = strlen(message);
+    char* copy = new (std::nothrow) char[length + 1];
+    if (!copy) {
+        return ALLOCATION_FAILED;
+    }
+    memcpy(copy, message, length + 1);
+    return copy;
+}
+
+// Status codes shared with `OsrmError::from_code`, 0 is success and 1 an unknown error.
+static int error_code(const std::string& code) {
+    static const std::unordered_map<std::string, int> codes = {
+        {"NoRoute", 2},
+        {"NoSegment", 3},
+        {"NoTable", 4},
+        {"NoTrips", 5},
+        {"NoMatch", 6},
+        {"InvalidOptions", 7},
+        {"InvalidQuery", 8},
+        {"InvalidValue", 9},
+        {"InvalidUrl", 10},
+        {"InvalidService", 11},
+        {"InvalidVersion", 12},
+        {"TooBig", 13},
+        {"NotImplemented", 14},
+        {"DisabledDataset", 15},
+    };
+
+    const auto it = codes.find(code);
+    return it == codes.end() ? 1 : it->second;
+}
+
+// Status code of a C++ exception caught at the FFI boundary.
+static const int EXCEPTION_CODE = 16;
+
+static OSRM_Result exception_result(const char* what) noexcept {
+    return {EXCEPTION_CODE, copy_message(what)};
+}
+
+static OSRM_Result instance_not_found() noexcept {
+    return {1, copy_message("OSRM instance not found")};
+}
+
+static OSRM_Result make_result(osrm::Status status, const osrm::json::Object& result) {
+    std::string result_str;
+    int code;
+
+    if (status == osrm::Status::Ok) {
+        code = 0;
+        osrm::util::json::render(result_str, result);
+    } else {
+        code = 1;
+        try {
+            code = error_code(variant_get<osrm::util::json::String>(result.values.at("code")).value);
+            result_str = variant_get<osrm::util::json::String>(result.values.at("message")).value;
+        } catch (const std::exception& e) {
+            result_str = "Unknown OSRM error";
+        }
+    }
+
+    return {code, copy_string(result_str)};
+}
+
+namespace fbresult = osrm::engine::api::fbresult;
+
+static bool same_location(const fbresult::Waypoint* source, const fbresult::Waypoint* destination) {
+    return source && destination && source->location() && destination->location() &&
+        source->location()->longitude() == destination->location()->longitude() &&
+        source->location()->latitude() == destination->location()->latitude();
+}
+
+// The flatbuffers tables write 0 for unreachable cells. A 0 between two different snapped
+// locations can only be one of those and is turned into NaN like the JSON null.
+static void copy_matrix(const flatbuffers::Vector<float>& values, const fbresult::FBResult& result,
+                        double* buffer, size_t rows, size_t columns) {
+    const auto* sources = result.waypoints();
+    const auto* destinations = result.table()->destinations();
+    for (size_t row = 0; row < rows; ++row) {
+        for (size_t column = 0; column < columns; ++column) {
+            const size_t index = row * columns + column;
+            if (index >= values.size()) {
+                return;
+            }
+            const double value = values.Get(index);
+            const bool unreachable = value == 0 && sources && destinations &&
+                row < sources->size() && column < destinations->size() &&
+                !same_location(sources->Get(row), destinations->Get(column));
+            buffer[index] = unreachable ? std::numeric_limits<double>::quiet_NaN() : value;
+        }
+    }
+}
+
+static void copy_waypoints(const flatbuffers::Vector<flatbuffers::Offset<fbresult::Waypoint>>& waypoints,
+                           OSRM_TableWaypoint* buffer, size_t capacity) {
+    for (size_t i = 0; i < waypoints.size() && i < capacity; ++i) {
+        const auto* waypoint = waypoints.Get(i);
+        if (waypoint->location()) {
+            buffer[i].longitude = waypoint->location()->longitude();
+            buffer[i].latitude = waypoint->location()->latitude();
+        }
+        buffer[i].distance = waypoint->distance();
+        buffer[i].name = copy_string(waypoint->name() ? waypoint->name()->str() : "");
+        if (waypoint->hint()) {
+            buffer[i].hint = copy_string(waypoint->hint()->str());
+        }
+    }
+}
 
-    struct OSRM_Result {
-        int code;
-        char* message;
+// Copies a successful table result into the caller buffers straight from OSRM's flatbuffers.
+static void copy_table(const fbresult::FBResult& result, OSRM_TableBuffers* buffers, size_t rows, size_t columns) {
+    const auto* table = result.table();
+    if (!table) {
+        return;
+    }
+
+    if (buffers->durations && table->durations()) {
+        copy_matrix(*table->durations(), result, buffers->durations, rows, columns);
+    }
+
+    if (buffers->distances && table->distances()) {
+        copy_matrix(*table->distances(), result, buffers->distances, rows, columns);
+    }
+
+    if (buffers->sources && result.waypoints()) {
+        copy_waypoints(*result.waypoints(), buffers->sources, rows);
+    }
+
+    if (buffers->destinations && table->destinations()) {
+        copy_waypoints(*table->destinations(), buffers->destinations, columns);
+    }
+
+    // Cells are flattened as row, column pairs.
+    const auto* fallback = table->fallback_speed_cells();
+    if (fallback && fallback->size() >= 2) {
+        const size_t cells = fallback->size() / 2;
+        buffers->fallback_speed_cells = new size_t[cells * 2];
+        buffers->num_fallback_speed_cells = cells;
+        for (size_t i = 0; i < cells * 2; ++i) {
+            buffers->fallback_speed_cells[i] = fallback->Get(i);
+        }
+    }
+}
+
+static OSRM_Result fb_error_result(const fbresult::FBResult& result) {
+    const auto* error = result.code();
+    if (!error) {
+        return {1, copy_string("Unknown OSRM error")};
+    }
+    return {
+        error->code() ? error_code(error->code()->str()) : 1,
+        copy_string(error->message() ? error->message()->str() : "Unknown OSRM error"),
     };
+}
+
+static OSRM_Buffer make_buffer(int code, const std::string& value) {
+    char* data = new char[value.size()];
+    memcpy(data, value.data(), value.size());
+    return {code, data, value.size()};
+}
+
+static OSRM_Buffer error_buffer(int code, const char* message) noexcept {
+    char* data = copy_message(message);
+    return {code, data, strlen(data)};
+}
+
+static void apply_engine_config(osrm::EngineConfig& config, const OSRM_EngineConfig* options) {
+    if (!options) {
+        return;
+    }
+
+    if (options->max_locations_trip >= 0) config.max_locations_trip = options->max_locations_trip;
+    if (options->max_locations_viaroute >= 0) config.max_locations_viaroute = options->max_locations_viaroute;
+    if (options->max_locations_distance_table >= 0) config.max_locations_distance_table = options->max_locations_distance_table;
+    if (options->max_locations_map_matching >= 0) config.max_locations_map_matching = options->max_locations_map_matching;
+    if (options->max_radius_map_matching >= 0) config.max_radius_map_matching = options->max_radius_map_matching;
+    if (options->max_results_nearest >= 0) config.max_results_nearest = options->max_results_nearest;
+    if (options->max_alternatives >= 0) config.max_alternatives = options->max_alternatives;
+    if (options->default_radius >= 0) config.default_radius = options->default_radius;
+    if (options->use_mmap >= 0) config.use_mmap = options->use_mmap == 1;
+    if (options->dataset_name) config.dataset_name = options->dataset_name;
+}
+
+// Every available core unless a positive count is requested, like the OSRM command line tools.
+static unsigned thread_count(int threads) {
+    return threads > 0 ? static_cast<unsigned>(threads) : std::thread::hardware_concurrency();
+}
+
+template <typename Config> static bool apply_pipeline_config(Config& config, const OSRM_PipelineConfig* options) {
+    config.UseDefaultOutputNames(options->base_path);
+    config.requested_num_threads = thread_count(options->threads);
+    return config.IsValid();
+}
+
+static void apply_updater_config(osrm::updater::UpdaterConfig& config, const OSRM_PipelineConfig* options) {
+    config.segment_speed_lookup_paths.assign(options->segment_speed_files,
+                                             options->segment_speed_files + options->num_segment_speed_files);
+    config.turn_penalty_lookup_paths.assign(options->turn_penalty_files,
+                                            options->turn_penalty_files + options->num_turn_penalty_files);
+}
+
+static OSRM_Result missing_files() {
+    return {1, copy_string("Dataset files are missing or invalid")};
+}
 
-    void* osrm_create(const char* base_path, const char* algorithm) {
+extern "C" {
+
+    // On failure returns null and sets `error` to a message released with `osrm_free_string`.
+    void* osrm_create(const char* base_path, const char* algorithm, const OSRM_EngineConfig* options, char** error) {
+        *error = nullptr;
         try {
             osrm::EngineConfig config;
-            config.storage_config = {base_path};
-            config.use_shared_memory = false;
+            config.use_shared_memory = options && options->use_shared_memory;
+            if (!config.use_shared_memory) {
+                config.storage_config = {base_path};
+            }
 
             if (strcmp(algorithm, "CH") == 0) {
                 config.algorithm = osrm::EngineConfig::Algorithm::CH;
@@ -34,170 +485,455 @@ extern "C" {
                config.algorithm = osrm::EngineConfig::Algorithm::MLD;
             }
 
+            apply_engine_config(config, options);
+
+            if (!config.IsValid()) {
+                *error = copy_string("Invalid engine configuration, are the dataset files present?");
+                return nullptr;
+            }
+
             return new osrm::OSRM(config);
         } catch (const std::exception& e) {
-            std::cerr << "Fail to create an OSRM instance: " << e.what() << std::endl;
+            *error = copy_message(e.what());
+            return nullptr;
+        } catch (...) {
+            *error = copy_message("Unknown C++ exception");
             return nullptr;
         }
     }
 
     void osrm_destroy(void* osrm_instance) {
-        if (osrm_instance) {
+        try {
             delete static_cast<osrm::OSRM*>(osrm_instance);
+        } catch (...) {
+            // Nothing can be reported from a destructor, never unwind into Rust.
         }
     }
 
     OSRM_Result osrm_table(void* osrm_instance,
-                          const double* coordinates,
+                          const OSRM_Coordinate* coordinates,
                           size_t num_coordinates,
                           const size_t* sources,
                           size_t num_sources,
                           const size_t* destinations,
-                          size_t num_destinations) {
+                          size_t num_destinations,
+                          const OSRM_TableOptions* options,
+                          OSRM_TableBuffers* buffers) {
 
         if (!osrm_instance) {
-            const char* err = "OSRM instance not found";
-            char* msg = new char[strlen(err) + 1];
-            strcpy(msg, err);
-            return {1, msg};
+            return instance_not_found();
         }
 
-        osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
-        osrm::TableParameters params;
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::TableParameters params;
 
-        for (size_t i = 0; i < num_coordinates; ++i) {
-            params.coordinates.push_back({
-                osrm::util::FloatLongitude{coordinates[i * 2]},
-                osrm::util::FloatLatitude{coordinates[i * 2 + 1]}
-            });
-        }
+            apply_coordinates(params, coordinates, num_coordinates);
 
-        if (num_sources > 0) {
-            params.sources.assign(sources, sources + num_sources);
+            if (num_sources > 0) {
+                params.sources.assign(sources, sources + num_sources);
+            }
+
+            if (num_destinations > 0) {
+                params.destinations.assign(destinations, destinations + num_destinations);
+            }
+
+            if (options) {
+                if (options->annotations != 0) {
+                    params.annotations = static_cast<osrm::TableParameters::AnnotationsType>(options->annotations);
+                }
+                if (options->has_fallback_speed) {
+                    params.fallback_speed = options->fallback_speed;
+                }
+                params.fallback_coordinate_type = options->fallback_coordinate == 1
+                    ? osrm::TableParameters::FallbackCoordinateType::Snapped
+                    : osrm::TableParameters::FallbackCoordinateType::Input;
+                params.scale_factor = options->scale_factor;
+            }
+
+            params.format = osrm::engine::api::BaseParameters::OutputFormatType::FLATBUFFERS;
+            osrm::engine::api::ResultT result = flatbuffers::FlatBufferBuilder();
+            const auto status = osrm_ptr->Table(params, result);
+            const auto& builder = variant_get<flatbuffers::FlatBufferBuilder>(result);
+            const auto* response = fbresult::GetFBResult(builder.GetBufferPointer());
+
+            if (status != osrm::Status::Ok || response->error()) {
+                return fb_error_result(*response);
+            }
+
+            if (buffers) {
+                const size_t rows = num_sources > 0 ? num_sources : num_coordinates;
+                const size_t columns = num_destinations > 0 ? num_destinations : num_coordinates;
+                copy_table(*response, buffers, rows, columns);
+            }
+
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
         }
+    }
 
-        if (num_destinations > 0) {
-            params.destinations.assign(destinations, destinations + num_destinations);
+    OSRM_Result osrm_route(void* osrm_instance,
+                           const OSRM_Coordinate* coordinates,
+                           size_t num_coordinates,
+                           const OSRM_RouteOptions* options)
+    {
+        if (!osrm_instance) {
+            return instance_not_found();
         }
 
-        osrm::json::Object result;
-        const auto status = osrm_ptr->Table(params, result);
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::RouteParameters params;
 
-        std::string result_str;
-        int code;
+            apply_coordinates(params, coordinates, num_coordinates);
 
-        if (status == osrm::Status::Ok) {
-            code = 0;
-            osrm::util::json::render(result_str, result);
-        } else {
-            code = 1;
-            try {
-                result_str = std::get<osrm::util::json::String>(result.values.at("message")).value;
-            } catch (const std::exception& e) {
-                result_str = "Unknown OSRM error";
-            }
+            apply_route_options(params, options);
+
+            osrm::json::Object result;
+            const auto status = osrm_ptr->Route(params, result);
+
+            return make_result(status, result);
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
         }
+    }
 
-    char* message = new char[result_str.length() + 1];
-    strcpy(message, result_str.c_str());
+    OSRM_Result osrm_trip(void* osrm_instance,
+                          const OSRM_Coordinate* coordinates,
+                          size_t num_coordinates,
+                          bool roundtrip,
+                          int source,
+                          int destination,
+                          const OSRM_RouteOptions* options)
+    {
+        if (!osrm_instance) {
+            return instance_not_found();
+        }
+
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::TripParameters params;
+
+            apply_coordinates(params, coordinates, num_coordinates);
 
-    return {code, message};
+            apply_route_options(params, options);
+
+            params.roundtrip = roundtrip;
+            params.source = source == 1 ? osrm::TripParameters::SourceType::First : osrm::TripParameters::SourceType::Any;
+            params.destination = destination == 1 ? osrm::TripParameters::DestinationType::Last : osrm::TripParameters::DestinationType::Any;
+
+            osrm::json::Object result;
+            const auto status = osrm_ptr->Trip(params, result);
+
+            return make_result(status, result);
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
     }
 
-    OSRM_Result osrm_route(void* osrm_instance,
-                           const double* coordinates,
-                           size_t num_coordinates)
+    OSRM_Result osrm_nearest(void* osrm_instance,
+                             const OSRM_Coordinate* coordinate,
+                             unsigned int number_of_results)
     {
         if (!osrm_instance) {
-            const char* err = "OSRM instance not found";
-            char* msg = new char[strlen(err) + 1];
-            strcpy(msg, err);
-            return {1, msg};
+            return instance_not_found();
         }
 
-        osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
-        osrm::RouteParameters params;
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::NearestParameters params;
+
+            apply_coordinates(params, coordinate, 1);
+            params.number_of_results = number_of_results;
+
+            osrm::json::Object result;
+            const auto status = osrm_ptr->Nearest(params, result);
 
-        for (size_t i = 0; i < num_coordinates; ++i) {
-            params.coordinates.push_back({
-                osrm::util::FloatLongitude{coordinates[i * 2]},
-                osrm::util::FloatLatitude{coordinates[i * 2 + 1]}
-            });
+            return make_result(status, result);
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
         }
+    }
 
-        osrm::json::Object result;
-        const auto status = osrm_ptr->Route(params, result);
+    OSRM_Result osrm_match(void* osrm_instance,
+                           const OSRM_Coordinate* coordinates,
+                           size_t num_coordinates,
+                           const unsigned int* timestamps,
+                           size_t num_timestamps,
+                           const size_t* waypoints,
+                           size_t num_waypoints,
+                           int gaps,
+                           bool tidy,
+                           const OSRM_RouteOptions* options)
+    {
+        if (!osrm_instance) {
+            return instance_not_found();
+        }
 
-        std::string result_str;
-        int code;
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::MatchParameters params;
 
-        if (status == osrm::Status::Ok) {
-            code = 0;
-            osrm::util::json::render(result_str, result);
-        } else {
-            code = 1;
-            try {
-                result_str = std::get<osrm::util::json::String>(result.values.at("message")).value;
-            } catch (const std::exception& e) {
-                result_str = "Unknown OSRM error";
+            apply_coordinates(params, coordinates, num_coordinates);
+
+            apply_route_options(params, options);
+
+            if (num_timestamps > 0) {
+                params.timestamps.assign(timestamps, timestamps + num_timestamps);
             }
+
+            if (num_waypoints > 0) {
+                params.waypoints.assign(waypoints, waypoints + num_waypoints);
+            }
+
+            params.gaps = gaps == 1 ? osrm::MatchParameters::GapsType::Ignore : osrm::MatchParameters::GapsType::Split;
+            params.tidy = tidy;
+
+            osrm::json::Object result;
+            const auto status = osrm_ptr->Match(params, result);
+
+            return make_result(status, result);
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
+
+    OSRM_Buffer osrm_tile(void* osrm_instance,
+                          unsigned int x,
+                          unsigned int y,
+                          unsigned int z)
+    {
+        if (!osrm_instance) {
+            return error_buffer(1, "OSRM instance not found");
         }
 
-        char* message = new char[result_str.length() + 1];
-        strcpy(message, result_str.c_str());
+        try {
+            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
+            osrm::TileParameters params{x, y, z};
+
+            if (!params.IsValid()) {
+                return make_buffer(error_code("InvalidValue"), "Invalid tile coordinates");
+            }
+
+            osrm::engine::api::ResultT result = std::string();
+            const auto status = osrm_ptr->Tile(params, result);
 
-        return {code, message};
+            if (status == osrm::Status::Ok) {
+                // The tile is binary PBF data and may contain null bytes.
+                return make_buffer(0, variant_get<std::string>(result));
+            }
+
+            int code = 1;
+            std::string message = "Unknown OSRM error";
+            if (const auto* object = variant_get_if<osrm::json::Object>(&result)) {
+                try {
+                    code = error_code(variant_get<osrm::util::json::String>(object->values.at("code")).value);
+                    message = variant_get<osrm::util::json::String>(object->values.at("message")).value;
+                } catch (const std::exception& e) {
+                }
+            }
+            return make_buffer(code, message);
+        } catch (const std::exception& e) {
+            return error_buffer(EXCEPTION_CODE, e.what());
+        } catch (...) {
+            return error_buffer(EXCEPTION_CODE, "Unknown C++ exception");
+        }
     }
 
-    OSRM_Result osrm_trip(void* osrm_instance,
-                          const double* coordinates,
-                          size_t num_coordinates)
+    OSRM_Result osrm_datastore_load(const char* base_path,
+                                    const char* dataset_name,
+                                    bool only_metric,
+                                    int max_wait)
     {
+        try {
+            osrm::storage::StorageConfig config(base_path, dataset_name);
+            if (!config.IsValid()) {
+                return missing_files();
+            }
 
-            if (!osrm_instance) {
-                const char* err = "OSRM instance not found";
-                char* msg = new char[strlen(err) + 1];
-                strcpy(msg, err);
-                return {1, msg};
+            osrm::storage::Storage storage(std::move(config));
+            if (storage.Run(max_wait, dataset_name, only_metric) != EXIT_SUCCESS) {
+                return {1, copy_string("Failed to load the dataset into shared memory")};
             }
 
-            osrm::OSRM* osrm_ptr = static_cast<osrm::OSRM*>(osrm_instance);
-            osrm::TripParameters params;
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
+
+    OSRM_Result osrm_datastore_list() {
+        using Monitor = osrm::storage::SharedMonitor<osrm::storage::SharedRegionRegister>;
+
+        try {
+            osrm::json::Array regions;
 
-            for (size_t i = 0; i < num_coordinates; ++i) {
-                params.coordinates.push_back({
-                    osrm::util::FloatLongitude{coordinates[i * 2]},
-                    osrm::util::FloatLatitude{coordinates[i * 2 + 1]}
-                });
+            if (Monitor::exists()) {
+                Monitor monitor;
+                const auto& shared_register = monitor.data();
+
+                std::vector<std::string> names;
+                shared_register.List(std::back_inserter(names));
+
+                for (const auto& name : names) {
+                    const auto region = shared_register.GetRegion(shared_register.Find(name));
+                    const auto memory = osrm::storage::makeSharedMemory(region.shm_key);
+
+                    osrm::json::Object entry;
+                    entry.values["name"] = osrm::json::String{name};
+                    entry.values["shm_key"] = osrm::json::Number{static_cast<double>(region.shm_key)};
+                    entry.values["timestamp"] = osrm::json::Number{static_cast<double>(region.timestamp)};
+                    entry.values["size"] = osrm::json::Number{static_cast<double>(memory->Size())};
+                    regions.values.push_back(std::move(entry));
+                }
             }
 
             osrm::json::Object result;
-            const auto status = osrm_ptr->Trip(params, result);
+            result.values["regions"] = std::move(regions);
 
             std::string result_str;
-            int code;
+            osrm::util::json::render(result_str, result);
+            return {0, copy_string(result_str)};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
 
-            if (status == osrm::Status::Ok) {
-                code = 0;
-                osrm::util::json::render(result_str, result);
-            } else {
-                code = 1;
-                try {
-                    result_str = std::get<osrm::util::json::String>(result.values.at("message")).value;
-                } catch (const std::exception& e) {
-                    result_str = "Unknown OSRM error";
+    OSRM_Result osrm_datastore_drop(const char* dataset_name) {
+        using Monitor = osrm::storage::SharedMonitor<osrm::storage::SharedRegionRegister>;
+
+        try {
+            if (!Monitor::exists()) {
+                return {1, copy_string("No dataset is loaded in shared memory")};
+            }
+
+            Monitor monitor;
+            auto& shared_register = monitor.data();
+            std::unique_lock lock(monitor.get_mutex());
+
+            bool found = false;
+            for (const auto* suffix : {"/static", "/updatable"}) {
+                const auto id = shared_register.Find(std::string(dataset_name) + suffix);
+                if (id == osrm::storage::SharedRegionRegister::INVALID_REGION_ID) {
+                    continue;
                 }
+
+                const auto key = shared_register.GetRegion(id).shm_key;
+                shared_register.Deregister(id);
+                shared_register.ReleaseKey(key);
+                osrm::storage::SharedMemory::Remove(key);
+                found = true;
             }
 
-            char* message = new char[result_str.length() + 1];
-            strcpy(message, result_str.c_str());
+            if (!found) {
+                return {1, copy_string("Dataset not found in shared memory")};
+            }
 
-            return {code, message};
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
         }
+    }
+
+    OSRM_Result osrm_extract(const OSRM_ExtractConfig* options) {
+        try {
+            osrm::ExtractorConfig config;
+            config.input_path = options->input_path;
+            config.profile_path = options->profile_path;
+            config.UseDefaultOutputNames(options->output_path ? options->output_path : options->input_path);
+            config.requested_num_threads = thread_count(options->threads);
+
+            osrm::extract(config);
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
+
+    OSRM_Result osrm_partition(const OSRM_PipelineConfig* options) {
+        try {
+            osrm::PartitionerConfig config;
+            if (!apply_pipeline_config(config, options)) {
+                return missing_files();
+            }
+
+            osrm::partition(config);
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
+
+    OSRM_Result osrm_customize(const OSRM_PipelineConfig* options) {
+        try {
+            osrm::CustomizationConfig config;
+            if (!apply_pipeline_config(config, options)) {
+                return missing_files();
+            }
+            apply_updater_config(config.updater_config, options);
+
+            osrm::customize(config);
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
+
+    OSRM_Result osrm_contract(const OSRM_PipelineConfig* options) {
+        try {
+            osrm::ContractorConfig config;
+            if (!apply_pipeline_config(config, options)) {
+                return missing_files();
+            }
+            apply_updater_config(config.updater_config, options);
+
+            osrm::contract(config);
+            return {0, copy_string("")};
+        } catch (const std::exception& e) {
+            return exception_result(e.what());
+        } catch (...) {
+            return exception_result("Unknown C++ exception");
+        }
+    }
 
     void osrm_free_string(char* s) {
-        if (s) {
+        if (s && s != ALLOCATION_FAILED) {
             delete[] s;
         }
     }
+
+    void osrm_free_indices(size_t* indices) {
+        if (indices) {
+            delete[] indices;
+        }
+    }
+
+    void osrm_free_buffer(char* data) {
+        if (data && data != ALLOCATION_FAILED) {
+            delete[] data;
+        }
+    }
 }
\ No newline at end of file